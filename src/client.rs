@@ -1,10 +1,15 @@
-use anyhow::{Context, Result};
-use reqwest::Client as InnerHttpClient;
+use anyhow::{Context, Result, anyhow};
+use reqwest::{Client as InnerHttpClient, Response, StatusCode};
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware as HttpClient};
-use reqwest_retry::RetryTransientMiddleware;
 use reqwest_retry::policies::ExponentialBackoff;
+use reqwest_retry::{
+    RetryTransientMiddleware, Retryable, RetryableStrategy, default_on_request_failure,
+    default_on_request_success,
+};
 use serde::Deserialize;
-use std::net::IpAddr;
+use smallvec::{SmallVec, smallvec};
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::task::{JoinHandle, JoinSet};
@@ -15,7 +20,7 @@ use tracing::{debug, error, info, warn};
 use crate::cache::Cache;
 use crate::config::{Config, NonEmptyString};
 use crate::ip::{IpUpdate, IpVersion};
-use crate::ip_lookup;
+use crate::ip_lookup::{self, DnsLookup, DnsRecord, RouterProtocol};
 
 static USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"),);
 
@@ -25,16 +30,124 @@ pub struct IpSourceInterface {
     name: NonEmptyString,
 }
 
+/// IP STUN source serde representation
+#[derive(Debug, Deserialize)]
+pub struct IpSourceStun {
+    #[serde(default = "default_stun_servers")]
+    pub(crate) servers: SmallVec<[String; 3]>,
+    #[serde(default = "default_stun_quorum")]
+    pub(crate) quorum: usize,
+}
+
+fn default_stun_servers() -> SmallVec<[String; 3]> {
+    smallvec![
+        "stun.cloudflare.com:3478".to_string(),
+        "stun.l.google.com:19302".to_string(),
+        "stun.nextcloud.com:3478".to_string(),
+    ]
+}
+
+fn default_stun_quorum() -> usize {
+    2
+}
+
+/// IP DNS source serde representation
+#[derive(Debug, Deserialize)]
+pub struct IpSourceDns {
+    #[serde(default = "default_dns_lookups")]
+    pub(crate) lookups: SmallVec<[DnsLookup; 3]>,
+    #[serde(default = "default_dns_quorum")]
+    pub(crate) quorum: usize,
+}
+
+fn default_dns_lookups() -> SmallVec<[DnsLookup; 3]> {
+    smallvec![
+        DnsLookup {
+            server: "resolver1.opendns.com".to_string(),
+            name: "myip.opendns.com".to_string(),
+            record: DnsRecord::Address,
+        },
+        DnsLookup {
+            server: "ns1.google.com".to_string(),
+            name: "o-o.myaddr.l.google.com".to_string(),
+            record: DnsRecord::Txt,
+        },
+        DnsLookup {
+            server: "one.one.one.one".to_string(),
+            name: "whoami.cloudflare".to_string(),
+            record: DnsRecord::ChaosTxt,
+        },
+    ]
+}
+
+fn default_dns_quorum() -> usize {
+    2
+}
+
+/// IP router source serde representation
+#[derive(Debug, Deserialize)]
+pub struct IpSourceRouter {
+    #[serde(default = "default_router_protocols")]
+    pub(crate) protocols: SmallVec<[RouterProtocol; 3]>,
+    pub(crate) gateway: Option<Ipv4Addr>,
+}
+
+fn default_router_protocols() -> SmallVec<[RouterProtocol; 3]> {
+    smallvec![
+        RouterProtocol::Upnp,
+        RouterProtocol::NatPmp,
+        RouterProtocol::Pcp,
+    ]
+}
+
+/// IP command source serde representation
+#[derive(Debug, Deserialize)]
+pub struct IpSourceCommand {
+    pub(crate) program: NonEmptyString,
+    #[serde(default)]
+    pub(crate) args: Vec<String>,
+    #[serde(default = "default_command_timeout", with = "humantime_serde")]
+    pub(crate) timeout: Duration,
+    #[serde(default)]
+    pub(crate) env: BTreeMap<String, String>,
+}
+
+fn default_command_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
 /// IP source for fetching the address
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum IpSource {
     Http,
     Interface(IpSourceInterface),
+    Stun(IpSourceStun),
+    Dns(IpSourceDns),
+    Router(IpSourceRouter),
+    Command(IpSourceCommand),
 }
 
 /// DDRS client
 #[derive(Debug)]
+/// Retries transient failures, except for throttled requests.
+///
+/// The backoff policy ignores `Retry-After`, so 429 responses are returned to
+/// the caller to wait out the delay the server asked for.
+pub struct RetryStrategy;
+
+impl RetryableStrategy for RetryStrategy {
+    fn handle(&self, result: &reqwest_middleware::Result<Response>) -> Option<Retryable> {
+        match result {
+            Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS => {
+                Some(Retryable::Fatal)
+            }
+            Ok(response) => default_on_request_success(response),
+            Err(error) => default_on_request_failure(error),
+        }
+    }
+}
+
 pub struct Client {
     config: Config,
     cache: Cache,
@@ -55,7 +168,10 @@ impl Client {
         let retry_policy =
             ExponentialBackoff::builder().build_with_max_retries(config.retries.get());
         let request = ClientBuilder::new(client)
-            .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+            .with(RetryTransientMiddleware::new_with_policy_and_strategy(
+                retry_policy,
+                RetryStrategy,
+            ))
             .build();
         Ok(Arc::new(Client {
             cache: Cache::new(config.cache_path.clone()),
@@ -88,28 +204,7 @@ impl Client {
                             v6: None,
                         };
                         for version in self.config.versions.iter() {
-                            let ip_result = match &self.config.source {
-                                IpSource::Http => {
-                                    let urls = match version {
-                                        IpVersion::V4 => &self.config.http_ipv4,
-                                        IpVersion::V6 => &self.config.http_ipv6,
-                                    };
-                                    ip_lookup::fetch_ip_http(
-                                        &self.request,
-                                        urls,
-                                        self.config.http_lookup_quorum.get(),
-                                        version,
-                                    )
-                                    .await
-                                    .context("failed to fetch IP via HTTP")
-                                },
-                                IpSource::Interface(interface) => ip_lookup::fetch_ip_interface(
-                                    interface.name.as_str(),
-                                    version,
-                                )
-                                .context("failed to fetch IP via interface"),
-                            };
-                            match ip_result {
+                            match self.fetch_ip(version).await {
                                 Ok(ip) => match version {
                                     IpVersion::V4 => {
                                         if let IpAddr::V4(ip) = ip {
@@ -171,6 +266,88 @@ impl Client {
         })
     }
 
+    /// Fetches the IP address from the version's sources, falling back to the
+    /// next source when one fails.
+    async fn fetch_ip(&self, version: IpVersion) -> Result<IpAddr> {
+        let sources = self.config.sources(version).as_slice();
+        if let [source] = sources {
+            return self.fetch_ip_from(source, version).await;
+        }
+
+        let mut failures = Vec::new();
+        for (index, source) in sources.iter().enumerate() {
+            match self.fetch_ip_from(source, version).await {
+                Ok(ip) => return Ok(ip),
+                Err(error) => {
+                    if index + 1 < sources.len() {
+                        warn!("{error:#}, falling back to the next {version} source");
+                    }
+                    failures.push(format!("{error:#}"));
+                }
+            }
+        }
+        Err(anyhow!(
+            "all IP sources failed for {version} [{}]",
+            failures.join("; ")
+        ))
+    }
+
+    async fn fetch_ip_from(&self, source: &IpSource, version: IpVersion) -> Result<IpAddr> {
+        match source {
+            IpSource::Http => {
+                let urls = match version {
+                    IpVersion::V4 => &self.config.http_ipv4,
+                    IpVersion::V6 => &self.config.http_ipv6,
+                };
+                ip_lookup::fetch_ip_http(
+                    &self.request,
+                    urls,
+                    self.config.http_lookup_quorum.get(),
+                    version,
+                )
+                .await
+                .context("failed to fetch IP via HTTP")
+            }
+            IpSource::Interface(interface) => {
+                ip_lookup::fetch_ip_interface(interface.name.as_str(), version)
+                    .context("failed to fetch IP via interface")
+            }
+            IpSource::Stun(stun) => ip_lookup::fetch_ip_stun(
+                &stun.servers,
+                stun.quorum,
+                version,
+                self.config.timeout.get(),
+            )
+            .await
+            .context("failed to fetch IP via STUN"),
+            IpSource::Dns(dns) => ip_lookup::fetch_ip_dns(
+                &dns.lookups,
+                dns.quorum,
+                version,
+                self.config.timeout.get(),
+            )
+            .await
+            .context("failed to fetch IP via DNS"),
+            IpSource::Router(router) => ip_lookup::fetch_ip_router(
+                &self.request,
+                &router.protocols,
+                router.gateway,
+                self.config.timeout.get(),
+            )
+            .await
+            .context("failed to fetch IP via router"),
+            IpSource::Command(command) => ip_lookup::fetch_ip_command(
+                command.program.as_str(),
+                &command.args,
+                &command.env,
+                command.timeout,
+                version,
+            )
+            .await
+            .context("failed to fetch IP via command"),
+        }
+    }
+
     async fn update_providers(&self, update: IpUpdate) -> bool {
         let mut set = JoinSet::new();
         for provider in &self.config.providers {
@@ -203,3 +380,94 @@ impl Client {
         self.shutdown.cancel();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use super::*;
+
+    const PROVIDER_CONFIG: &str = r#"
+[[providers]]
+type = "cloudflare"
+zone = "example.com"
+api_token = "token"
+
+[[providers.domains]]
+name = "example.com"
+"#;
+
+    fn client(config: &str) -> Arc<Client> {
+        let config: Config = toml::from_str(&format!("{config}\n{PROVIDER_CONFIG}")).unwrap();
+        Client::new(config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_falls_back_to_next_source() {
+        let client = client(
+            r#"
+[[source]]
+type = "command"
+program = "sh"
+args = ["-c", "exit 1"]
+
+[[source]]
+type = "command"
+program = "sh"
+args = ["-c", "echo 192.0.2.10"]
+"#,
+        );
+
+        let ip = client.fetch_ip(IpVersion::V4).await.unwrap();
+
+        assert_eq!(ip, IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10)));
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_uses_per_version_sources() {
+        let client = client(
+            r#"
+versions = ["v4", "v6"]
+
+[source_ipv4]
+type = "command"
+program = "sh"
+args = ["-c", "echo 192.0.2.10"]
+
+[source_ipv6]
+type = "command"
+program = "sh"
+args = ["-c", "echo 2001:db8::1"]
+"#,
+        );
+
+        let v4 = client.fetch_ip(IpVersion::V4).await.unwrap();
+        let v6 = client.fetch_ip(IpVersion::V6).await.unwrap();
+
+        assert_eq!(v4, IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10)));
+        assert_eq!(v6, IpAddr::V6("2001:db8::1".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_reports_every_failed_source() {
+        let client = client(
+            r#"
+[[source]]
+type = "interface"
+name = "ddrs-missing0"
+
+[[source]]
+type = "command"
+program = "sh"
+args = ["-c", "exit 2"]
+"#,
+        );
+
+        let error = client.fetch_ip(IpVersion::V4).await.unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "all IP sources failed for IPv4 [failed to fetch IP via interface: failed to find network interface: ddrs-missing0; failed to fetch IP via command: sh exited with exit status: 2]"
+        );
+    }
+}
@@ -7,6 +7,7 @@ use url::Url;
 
 use crate::client::IpSource;
 use crate::ip::IpVersion;
+use crate::ip_lookup;
 use crate::providers::Provider;
 
 const MAX_RETRIES: u32 = 10;
@@ -17,8 +18,12 @@ const MAX_RETRIES: u32 = 10;
 pub struct Config {
     /// Interval between IP address checks
     pub interval: NonZeroDuration,
-    /// Source for address checks
-    pub source: IpSource,
+    /// Sources for address checks, tried in order
+    pub source: IpSources,
+    /// Sources for IPv4 address checks, overriding `source`
+    pub source_ipv4: Option<IpSources>,
+    /// Sources for IPv6 address checks, overriding `source`
+    pub source_ipv6: Option<IpSources>,
     /// IP versions to check/update
     pub versions: IpVersions,
     /// Toggle dry run mode
@@ -46,7 +51,9 @@ pub struct Config {
 struct RawConfig {
     #[serde(with = "humantime_serde")]
     interval: Duration,
-    source: IpSource,
+    source: IpSources,
+    source_ipv4: Option<IpSources>,
+    source_ipv6: Option<IpSources>,
     versions: SmallVec<[IpVersion; 2]>,
     dry_run: bool,
     #[serde(with = "humantime_serde")]
@@ -65,7 +72,9 @@ impl Default for RawConfig {
     fn default() -> Self {
         Self {
             interval: Duration::from_mins(1),
-            source: IpSource::Http,
+            source: IpSources(smallvec![IpSource::Http]),
+            source_ipv4: None,
+            source_ipv6: None,
             versions: smallvec![IpVersion::V4],
             dry_run: false,
             timeout: Duration::from_secs(10),
@@ -107,32 +116,37 @@ impl TryFrom<RawConfig> for Config {
         ensure_http_urls("http_ipv4", &raw.http_ipv4)?;
         ensure_http_urls("http_ipv6", &raw.http_ipv6)?;
 
-        if matches!(raw.source, IpSource::Http) {
-            if versions.contains(IpVersion::V4) && raw.http_ipv4.is_empty() {
-                return Err(anyhow!(
-                    "http_ipv4 must not be empty when source is http and versions includes v4"
-                ));
-            }
-            if versions.contains(IpVersion::V4) {
-                ensure_http_lookup_quorum("http_ipv4", &raw.http_ipv4, http_lookup_quorum)?;
-            }
-            if versions.contains(IpVersion::V6) && raw.http_ipv6.is_empty() {
-                return Err(anyhow!(
-                    "http_ipv6 must not be empty when source is http and versions includes v6"
-                ));
-            }
-            if versions.contains(IpVersion::V6) {
-                ensure_http_lookup_quorum("http_ipv6", &raw.http_ipv6, http_lookup_quorum)?;
-            }
+        for version in versions.iter() {
+            let (field, sources, http_field, urls) = match version {
+                IpVersion::V4 => ("source_ipv4", &raw.source_ipv4, "http_ipv4", &raw.http_ipv4),
+                IpVersion::V6 => ("source_ipv6", &raw.source_ipv6, "http_ipv6", &raw.http_ipv6),
+            };
+            let (field, sources) = match sources {
+                Some(sources) => (field, sources),
+                None => ("source", &raw.source),
+            };
+            ensure_sources(
+                field,
+                sources,
+                version,
+                http_field,
+                urls,
+                http_lookup_quorum,
+            )?;
         }
 
         if raw.providers.is_empty() {
             return Err(anyhow!("no providers configured"));
         }
+        for provider in &raw.providers {
+            provider.validate_versions(versions)?;
+        }
 
         Ok(Self {
             interval,
             source: raw.source,
+            source_ipv4: raw.source_ipv4,
+            source_ipv6: raw.source_ipv6,
             versions,
             dry_run: raw.dry_run,
             timeout,
@@ -147,6 +161,61 @@ impl TryFrom<RawConfig> for Config {
     }
 }
 
+impl Config {
+    /// Returns the sources to try, in order, for an IP version.
+    pub fn sources(&self, version: IpVersion) -> &IpSources {
+        let sources = match version {
+            IpVersion::V4 => self.source_ipv4.as_ref(),
+            IpVersion::V6 => self.source_ipv6.as_ref(),
+        };
+        sources.unwrap_or(&self.source)
+    }
+}
+
+/// Ordered IP sources, configured as a single table or an array of tables
+#[derive(Debug)]
+pub struct IpSources(SmallVec<[IpSource; 2]>);
+
+impl IpSources {
+    pub fn as_slice(&self) -> &[IpSource] {
+        &self.0
+    }
+}
+
+impl<'de> Deserialize<'de> for IpSources {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct IpSourcesVisitor;
+
+        impl<'de> de::Visitor<'de> for IpSourcesVisitor {
+            type Value = IpSources;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("an IP source table or an array of IP source tables")
+            }
+
+            fn visit_map<A>(self, map: A) -> std::result::Result<Self::Value, A::Error>
+            where
+                A: de::MapAccess<'de>,
+            {
+                let source = IpSource::deserialize(de::value::MapAccessDeserializer::new(map))?;
+                Ok(IpSources(smallvec![source]))
+            }
+
+            fn visit_seq<A>(self, seq: A) -> std::result::Result<Self::Value, A::Error>
+            where
+                A: de::SeqAccess<'de>,
+            {
+                SmallVec::deserialize(de::value::SeqAccessDeserializer::new(seq)).map(IpSources)
+            }
+        }
+
+        deserializer.deserialize_any(IpSourcesVisitor)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct NonZeroDuration(Duration);
 
@@ -282,23 +351,137 @@ fn ensure_http_lookup_quorum(
     urls: &[Url],
     http_lookup_quorum: HttpLookupQuorum,
 ) -> Result<()> {
-    let url_count = urls.len();
-    if http_lookup_quorum.get() > url_count {
+    ensure_lookup_quorum(
+        "http_lookup_quorum",
+        http_lookup_quorum.get(),
+        &format!("{field} URL"),
+        &format!("{field} URLs"),
+        urls.len(),
+    )
+}
+
+/// Checks each source of the chain used for `version`.
+fn ensure_sources(
+    field: &str,
+    sources: &IpSources,
+    version: IpVersion,
+    http_field: &str,
+    urls: &[Url],
+    http_lookup_quorum: HttpLookupQuorum,
+) -> Result<()> {
+    if sources.0.is_empty() {
+        return Err(anyhow!("{field} must not be empty"));
+    }
+    for source in &sources.0 {
+        match source {
+            IpSource::Http => {
+                if urls.is_empty() {
+                    let version = match version {
+                        IpVersion::V4 => "v4",
+                        IpVersion::V6 => "v6",
+                    };
+                    return Err(anyhow!(
+                        "{http_field} must not be empty when {field} is http and versions includes {version}"
+                    ));
+                }
+                ensure_http_lookup_quorum(http_field, urls, http_lookup_quorum)?;
+            }
+            IpSource::Router(router) => {
+                if router.protocols.is_empty() {
+                    return Err(anyhow!(
+                        "{field}.protocols must not be empty when {field} is router"
+                    ));
+                }
+                if version == IpVersion::V6 {
+                    let hint = if field == "source" {
+                        "remove v6 from versions".to_string()
+                    } else {
+                        format!("remove it from {field}")
+                    };
+                    return Err(anyhow!("router source only supports IPv4, {hint}"));
+                }
+            }
+            IpSource::Command(command) => {
+                NonZeroDuration::new(command.timeout, &format!("{field}.timeout"))?;
+            }
+            IpSource::Interface(_) | IpSource::Stun(_) | IpSource::Dns(_) => {}
+        }
+        ensure_lookup_source(field, source)?;
+    }
+    Ok(())
+}
+
+/// Checks the lookups and quorum of the STUN and DNS sources.
+fn ensure_lookup_source(field: &str, source: &IpSource) -> Result<()> {
+    match source {
+        IpSource::Stun(stun) => {
+            if stun.servers.is_empty() {
+                return Err(anyhow!(
+                    "{field}.servers must not be empty when {field} is stun"
+                ));
+            }
+            for server in &stun.servers {
+                ip_lookup::split_stun_server(server)?;
+            }
+            ensure_lookup_quorum(
+                &format!("{field}.quorum"),
+                stun.quorum,
+                &format!("{field}.servers"),
+                &format!("{field}.servers"),
+                stun.servers.len(),
+            )
+        }
+        IpSource::Dns(dns) => {
+            if dns.lookups.is_empty() {
+                return Err(anyhow!(
+                    "{field}.lookups must not be empty when {field} is dns"
+                ));
+            }
+            for lookup in &dns.lookups {
+                ip_lookup::validate_dns_lookup(lookup)?;
+            }
+            ensure_lookup_quorum(
+                &format!("{field}.quorum"),
+                dns.quorum,
+                &format!("{field}.lookups"),
+                &format!("{field}.lookups"),
+                dns.lookups.len(),
+            )
+        }
+        IpSource::Http | IpSource::Interface(_) | IpSource::Router(_) | IpSource::Command(_) => {
+            Ok(())
+        }
+    }
+}
+
+/// Checks that a quorum is reachable and that no two disjoint sets of lookups
+/// can both reach it.
+fn ensure_lookup_quorum(
+    quorum_field: &str,
+    quorum: usize,
+    lookup: &str,
+    lookups: &str,
+    lookup_count: usize,
+) -> Result<()> {
+    if quorum == 0 {
+        return Err(anyhow!("{quorum_field} must be greater than 0"));
+    }
+    if quorum > lookup_count {
         return Err(anyhow!(
-            "http_lookup_quorum must not be greater than {field} URL count ({url_count})"
+            "{quorum_field} must not be greater than {lookup} count ({lookup_count})"
         ));
     }
-    let majority = http_lookup_majority(url_count);
-    if http_lookup_quorum.get() < majority {
+    let majority = lookup_majority(lookup_count);
+    if quorum < majority {
         return Err(anyhow!(
-            "http_lookup_quorum must be at least a majority of {field} URLs ({majority} for {url_count})"
+            "{quorum_field} must be at least a majority of {lookups} ({majority} for {lookup_count})"
         ));
     }
     Ok(())
 }
 
-fn http_lookup_majority(url_count: usize) -> usize {
-    (url_count / 2) + 1
+fn lookup_majority(lookup_count: usize) -> usize {
+    (lookup_count / 2) + 1
 }
 
 fn parse_default_url(url: &str) -> Url {
@@ -308,7 +491,10 @@ fn parse_default_url(url: &str) -> Url {
 
 #[cfg(test)]
 mod tests {
+    use std::net::Ipv4Addr;
+
     use super::*;
+    use crate::ip_lookup::{DnsRecord, RouterProtocol};
 
     const PROVIDER_CONFIG: &str = r#"
 [[providers]]
@@ -444,7 +630,7 @@ name = "en0"
         )
         .unwrap();
 
-        assert!(matches!(config.source, IpSource::Interface(_)));
+        assert!(matches!(config.source.as_slice(), [IpSource::Interface(_)]));
         assert!(config.http_ipv4.is_empty());
         assert!(config.http_ipv6.is_empty());
     }
@@ -462,6 +648,325 @@ name = ""
         assert!(error.contains("must not be empty"));
     }
 
+    #[test]
+    fn parses_stun_source_with_defaults() {
+        let config = parse_config(
+            r#"
+versions = ["v4", "v6"]
+http_ipv4 = []
+http_ipv6 = []
+
+[source]
+type = "stun"
+"#,
+        )
+        .unwrap();
+
+        let [IpSource::Stun(stun)] = config.source.as_slice() else {
+            panic!("expected STUN source");
+        };
+        assert_eq!(stun.servers.len(), 3);
+        assert_eq!(stun.quorum, 2);
+    }
+
+    #[test]
+    fn rejects_invalid_stun_source() {
+        let empty_error = parse_error(
+            r#"
+[source]
+type = "stun"
+servers = []
+"#,
+        );
+        let port_error = parse_error(
+            r#"
+[source]
+type = "stun"
+servers = ["stun.example.com:stun"]
+quorum = 1
+"#,
+        );
+        let quorum_error = parse_error(
+            r#"
+[source]
+type = "stun"
+servers = ["one.example.com", "two.example.com", "three.example.com"]
+quorum = 1
+"#,
+        );
+
+        assert!(empty_error.contains("source.servers must not be empty when source is stun"));
+        assert!(port_error.contains("invalid STUN server stun.example.com:stun: invalid port"));
+        assert!(
+            quorum_error
+                .contains("source.quorum must be at least a majority of source.servers (2 for 3)")
+        );
+    }
+
+    #[test]
+    fn parses_dns_source() {
+        let default_config = parse_config(
+            r#"
+[source]
+type = "dns"
+"#,
+        )
+        .unwrap();
+        let custom_config = parse_config(
+            r#"
+[source]
+type = "dns"
+quorum = 1
+
+[[source.lookups]]
+server = "127.0.0.1:5353"
+name = "whoami.example"
+record = "chaos_txt"
+"#,
+        )
+        .unwrap();
+
+        let [IpSource::Dns(defaults)] = default_config.source.as_slice() else {
+            panic!("expected DNS source");
+        };
+        assert_eq!(defaults.lookups.len(), 3);
+        assert_eq!(defaults.quorum, 2);
+        let [IpSource::Dns(custom)] = custom_config.source.as_slice() else {
+            panic!("expected DNS source");
+        };
+        assert_eq!(custom.lookups[0].server, "127.0.0.1:5353");
+        assert_eq!(custom.lookups[0].record, DnsRecord::ChaosTxt);
+    }
+
+    #[test]
+    fn rejects_invalid_dns_source() {
+        let empty_error = parse_error(
+            r#"
+[source]
+type = "dns"
+lookups = []
+"#,
+        );
+        let name_error = parse_error(
+            r#"
+[source]
+type = "dns"
+quorum = 1
+
+[[source.lookups]]
+server = "127.0.0.1"
+name = "bad..name"
+record = "txt"
+"#,
+        );
+        let quorum_error = parse_error(
+            r#"
+[source]
+type = "dns"
+quorum = 4
+"#,
+        );
+
+        assert!(empty_error.contains("source.lookups must not be empty when source is dns"));
+        assert!(name_error.contains("invalid DNS label in name (bad..name)"));
+        assert!(
+            quorum_error
+                .contains("source.quorum must not be greater than source.lookups count (3)")
+        );
+    }
+
+    #[test]
+    fn parses_router_source() {
+        let config = parse_config(
+            r#"
+[source]
+type = "router"
+protocols = ["nat_pmp", "pcp"]
+gateway = "192.168.1.1"
+"#,
+        )
+        .unwrap();
+
+        let [IpSource::Router(router)] = config.source.as_slice() else {
+            panic!("expected router source");
+        };
+        assert_eq!(
+            router.protocols.as_slice(),
+            [RouterProtocol::NatPmp, RouterProtocol::Pcp]
+        );
+        assert_eq!(router.gateway, Some(Ipv4Addr::new(192, 168, 1, 1)));
+    }
+
+    #[test]
+    fn rejects_invalid_router_source() {
+        let empty_error = parse_error(
+            r#"
+[source]
+type = "router"
+protocols = []
+"#,
+        );
+        let version_error = parse_error(
+            r#"
+versions = ["v4", "v6"]
+http_ipv6 = []
+
+[source]
+type = "router"
+"#,
+        );
+
+        assert!(empty_error.contains("source.protocols must not be empty when source is router"));
+        assert!(
+            version_error.contains("router source only supports IPv4, remove v6 from versions")
+        );
+    }
+
+    #[test]
+    fn parses_command_source() {
+        let config = parse_config(
+            r#"
+[source]
+type = "command"
+program = "/usr/local/bin/wan-ip"
+args = ["--interface", "ppp0"]
+timeout = "5s"
+env = { ROUTER_HOST = "192.168.1.1" }
+"#,
+        )
+        .unwrap();
+
+        let [IpSource::Command(command)] = config.source.as_slice() else {
+            panic!("expected command source");
+        };
+        assert_eq!(command.program.as_str(), "/usr/local/bin/wan-ip");
+        assert_eq!(command.args, ["--interface", "ppp0"]);
+        assert_eq!(command.timeout, Duration::from_secs(5));
+        assert_eq!(command.env["ROUTER_HOST"], "192.168.1.1");
+    }
+
+    #[test]
+    fn rejects_invalid_command_source() {
+        let program_error = parse_error(
+            r#"
+[source]
+type = "command"
+program = ""
+"#,
+        );
+        let timeout_error = parse_error(
+            r#"
+[source]
+type = "command"
+program = "wan-ip"
+timeout = "0s"
+"#,
+        );
+
+        assert!(
+            program_error.contains("must not be empty"),
+            "{program_error}"
+        );
+        assert!(timeout_error.contains("source.timeout must be greater than 0s"));
+    }
+
+    #[test]
+    fn parses_source_chains_and_per_version_sources() {
+        let config = parse_config(
+            r#"
+versions = ["v4", "v6"]
+http_ipv6 = []
+
+[[source]]
+type = "interface"
+name = "eth0"
+
+[[source]]
+type = "http"
+
+[source_ipv6]
+type = "interface"
+name = "eth0"
+"#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            config.sources(IpVersion::V4).as_slice(),
+            [IpSource::Interface(_), IpSource::Http]
+        ));
+        assert!(matches!(
+            config.sources(IpVersion::V6).as_slice(),
+            [IpSource::Interface(_)]
+        ));
+    }
+
+    #[test]
+    fn validates_sources_used_for_each_version() {
+        let empty_error = parse_error("source = []");
+        let http_error = parse_error(
+            r#"
+versions = ["v4", "v6"]
+http_ipv6 = []
+
+[[source_ipv6]]
+type = "interface"
+name = "eth0"
+
+[[source_ipv6]]
+type = "http"
+"#,
+        );
+        let router_error = parse_error(
+            r#"
+versions = ["v4", "v6"]
+
+[[source_ipv6]]
+type = "router"
+"#,
+        );
+        let stun_error = parse_error(
+            r#"
+[[source_ipv4]]
+type = "stun"
+servers = []
+"#,
+        );
+
+        assert!(empty_error.contains("source must not be empty"));
+        assert!(http_error.contains(
+            "http_ipv6 must not be empty when source_ipv6 is http and versions includes v6"
+        ));
+        assert!(
+            router_error.contains("router source only supports IPv4, remove it from source_ipv6")
+        );
+        assert!(
+            stun_error.contains("source_ipv4.servers must not be empty when source_ipv4 is stun")
+        );
+    }
+
+    #[test]
+    fn allows_router_source_for_ipv4_only() {
+        let config = parse_config(
+            r#"
+versions = ["v4", "v6"]
+
+[source_ipv4]
+type = "router"
+"#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            config.sources(IpVersion::V4).as_slice(),
+            [IpSource::Router(_)]
+        ));
+        assert!(matches!(
+            config.sources(IpVersion::V6).as_slice(),
+            [IpSource::Http]
+        ));
+    }
+
     #[test]
     fn rejects_retry_count_above_limit() {
         let error = parse_error("retries = 11");
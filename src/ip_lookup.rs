@@ -1,16 +1,29 @@
 use std::collections::BTreeMap;
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 
 use anyhow::{Context, Result, anyhow};
 use local_ip_address::list_afinet_netifas;
 use reqwest::Response;
 use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use tokio::net::{UdpSocket, lookup_host};
 use tokio::task::JoinSet;
 use tracing::debug;
 use url::Url;
 
 use crate::ip::IpVersion;
 
+mod command;
+mod dns;
+mod router;
+mod stun;
+
+pub use command::fetch_ip_command;
+pub(crate) use dns::validate_lookup as validate_dns_lookup;
+pub use dns::{DnsLookup, DnsRecord, fetch_ip_dns};
+pub use router::{RouterProtocol, fetch_ip_router};
+pub use stun::fetch_ip_stun;
+pub(crate) use stun::split_server as split_stun_server;
+
 const MAX_IP_LOOKUP_BODY_BYTES: usize = 256;
 
 /// Fetches the IP address via HTTP lookup URLs.
@@ -20,7 +33,6 @@ pub async fn fetch_ip_http(
     threshold: usize,
     version: IpVersion,
 ) -> Result<IpAddr> {
-    let url_count = urls.len();
     let mut set = JoinSet::new();
     for url in urls {
         let request = request.clone();
@@ -35,6 +47,18 @@ pub async fn fetch_ip_http(
         });
     }
 
+    collect_ip_quorum(set, threshold, version, "HTTP", "URLs").await
+}
+
+/// Collects lookup results until `threshold` of them agree on an address.
+async fn collect_ip_quorum(
+    mut set: JoinSet<Result<IpAddr>>,
+    threshold: usize,
+    version: IpVersion,
+    source: &str,
+    lookups: &str,
+) -> Result<IpAddr> {
+    let lookup_count = set.len();
     let mut votes = BTreeMap::new();
     let mut failures = 0;
     let mut failure_details = Vec::new();
@@ -49,7 +73,7 @@ pub async fn fetch_ip_http(
                 if vote_count >= threshold {
                     let checked = votes.values().sum::<usize>() + failures;
                     debug!(
-                        "IP lookup quorum reached for {version}: {ip} ({vote_count}/{checked} responses, {threshold}/{url_count} required)"
+                        "IP lookup quorum reached for {version}: {ip} ({vote_count}/{checked} responses, {threshold}/{lookup_count} required)"
                     );
                     set.abort_all();
                     return Ok(ip);
@@ -74,13 +98,82 @@ pub async fn fetch_ip_http(
             Ok(ip)
         }
         IpQuorumResult::NotReached { votes, failures } => Err(anyhow!(
-            "no HTTP IP lookup quorum reached for {version}: required {threshold} matching responses from {url_count} URLs, votes [{}], failures {failures}{}",
+            "no {source} IP lookup quorum reached for {version}: required {threshold} matching responses from {lookup_count} {lookups}, votes [{}], failures {failures}{}",
             format_ip_votes(&votes),
             format_failure_details(&failure_details)
         )),
     }
 }
 
+/// Splits a `host[:port]` server into its host and port, accepting bracketed
+/// IPv6 literals and falling back to `default_port`.
+fn split_host_port<'a>(
+    server: &'a str,
+    default_port: u16,
+    protocol: &str,
+) -> Result<(&'a str, u16)> {
+    let (host, port) = if let Some(rest) = server.strip_prefix('[') {
+        let (host, port) = rest.split_once(']').ok_or_else(|| {
+            anyhow!("invalid {protocol} server {server}: missing closing bracket")
+        })?;
+        let port = match port {
+            "" => None,
+            port => Some(
+                port.strip_prefix(':')
+                    .ok_or_else(|| anyhow!("invalid {protocol} server {server}"))?,
+            ),
+        };
+        (host, port)
+    } else {
+        match server.split_once(':') {
+            // More than one colon is a bare IPv6 literal
+            Some((host, port)) if !port.contains(':') => (host, Some(port)),
+            _ => (server, None),
+        }
+    };
+
+    if host.is_empty() {
+        return Err(anyhow!("invalid {protocol} server {server}: missing host"));
+    }
+    let port = match port {
+        Some(port) => port
+            .parse()
+            .map_err(|_| anyhow!("invalid {protocol} server {server}: invalid port"))?,
+        None => default_port,
+    };
+    Ok((host, port))
+}
+
+/// Resolves a `host[:port]` server to an address of the given IP version.
+async fn resolve_host_port(
+    server: &str,
+    default_port: u16,
+    protocol: &str,
+    version: IpVersion,
+) -> Result<SocketAddr> {
+    let (host, port) = split_host_port(server, default_port, protocol)?;
+    lookup_host((host, port))
+        .await
+        .with_context(|| format!("failed to resolve {protocol} server {server}"))?
+        .find(|address| match version {
+            IpVersion::V4 => address.is_ipv4(),
+            IpVersion::V6 => address.is_ipv6(),
+        })
+        .ok_or_else(|| anyhow!("no {version} address found for {protocol} server {server}"))
+}
+
+/// Connects a UDP socket to `address` from the wildcard address of the same
+/// family, so the server sees the public address for that IP version.
+async fn connect_udp(address: SocketAddr) -> Result<UdpSocket> {
+    let bind: SocketAddr = match address {
+        SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+        SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+    };
+    let socket = UdpSocket::bind(bind).await?;
+    socket.connect(address).await?;
+    Ok(socket)
+}
+
 /// Fetches the IP address of a specific network interface.
 pub fn fetch_ip_interface(interface_name: &str, version: IpVersion) -> Result<IpAddr> {
     let interfaces = list_afinet_netifas()?;
@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use tokio::process::Command;
+use tokio::time;
+
+use crate::ip::IpVersion;
+
+use super::parse_ip_for_version;
+
+/// Environment variable telling the command which IP version to print
+const VERSION_ENV: &str = "DDRS_IP_VERSION";
+
+/// Fetches the IP address from the stdout of a program.
+pub async fn fetch_ip_command(
+    program: &str,
+    args: &[String],
+    env: &BTreeMap<String, String>,
+    timeout: Duration,
+    version: IpVersion,
+) -> Result<IpAddr> {
+    let version_env = match version {
+        IpVersion::V4 => "v4",
+        IpVersion::V6 => "v6",
+    };
+    let child = Command::new(program)
+        .args(args)
+        .envs(env)
+        .env(VERSION_ENV, version_env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Dropping the output future on timeout kills the program
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("failed to run {program}"))?;
+
+    let output = time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| anyhow!("{program} timed out after {timeout:?}"))?
+        .with_context(|| format!("failed to read output of {program}"))?;
+
+    if !output.status.success() {
+        let detail = body_snippet(&String::from_utf8_lossy(&output.stderr))
+            .map(|snippet| format!(": {snippet}"))
+            .unwrap_or_default();
+        return Err(anyhow!("{program} exited with {}{detail}", output.status));
+    }
+
+    let stdout = String::from_utf8(output.stdout)
+        .with_context(|| format!("{program} output was not valid UTF-8"))?;
+    parse_ip_for_version(version, &stdout)
+        .with_context(|| format!("failed to parse {version:?} IP from {program} output"))
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    use std::time::Duration;
+
+    use crate::ip::IpVersion;
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    async fn run_script(script: &str, version: IpVersion) -> Result<IpAddr> {
+        let args = ["-c".to_string(), script.to_string()];
+        let env = BTreeMap::from([("WAN_IPV4".to_string(), "192.0.2.10".to_string())]);
+        fetch_ip_command("sh", &args, &env, TIMEOUT, version).await
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_command_parses_stdout() {
+        let script =
+            r#"if [ "$DDRS_IP_VERSION" = v6 ]; then echo 2001:db8::1; else echo "$WAN_IPV4"; fi"#;
+
+        let v4 = run_script(script, IpVersion::V4).await.unwrap();
+        let v6 = run_script(script, IpVersion::V6).await.unwrap();
+
+        assert_eq!(v4, IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10)));
+        assert_eq!(
+            v6,
+            IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1))
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_command_reports_non_zero_exit() {
+        let error = run_script("echo 'router unreachable' >&2; exit 3", IpVersion::V4)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "sh exited with exit status: 3: router unreachable"
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_command_times_out() {
+        let args = ["-c".to_string(), "sleep 5".to_string()];
+
+        let error = fetch_ip_command(
+            "sh",
+            &args,
+            &BTreeMap::new(),
+            Duration::from_millis(100),
+            IpVersion::V4,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(error.to_string(), "sh timed out after 100ms");
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_command_rejects_address_of_other_version() {
+        let error = run_script("echo 2001:db8::1", IpVersion::V4)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            format!("{error:#}"),
+            "failed to parse V4 IP from sh output: expected IPv4 address: invalid IPv4 address syntax"
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_command_reports_missing_program() {
+        let error = fetch_ip_command(
+            "/nonexistent/ddrs-ip",
+            &[],
+            &BTreeMap::new(),
+            TIMEOUT,
+            IpVersion::V4,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(error.to_string(), "failed to run /nonexistent/ddrs-ip");
+    }
+}
@@ -0,0 +1,546 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::Deserialize;
+use tokio::net::UdpSocket;
+use tokio::task::JoinSet;
+use tokio::time;
+
+use crate::ip::IpVersion;
+
+use super::{
+    collect_ip_quorum, connect_udp, parse_ip_for_version, resolve_host_port, split_host_port,
+};
+
+const DEFAULT_PORT: u16 = 53;
+const HEADER_LEN: usize = 12;
+const MAX_UDP_MESSAGE_BYTES: usize = 512;
+const MAX_NAME_BYTES: usize = 255;
+const MAX_LABEL_BYTES: usize = 63;
+const INITIAL_RTO: Duration = Duration::from_secs(1);
+
+const FLAG_RESPONSE: u16 = 1 << 15;
+const FLAG_TRUNCATED: u16 = 1 << 9;
+const FLAG_RECURSION_DESIRED: u16 = 1 << 8;
+
+const TYPE_A: u16 = 1;
+const TYPE_TXT: u16 = 16;
+const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+const CLASS_CH: u16 = 3;
+
+/// Record queried to learn the address a DNS server sees
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DnsRecord {
+    /// `A` or `AAAA` record matching the IP version, e.g. `myip.opendns.com`
+    Address,
+    /// `IN TXT` record, e.g. `o-o.myaddr.l.google.com`
+    Txt,
+    /// `CH TXT` record, e.g. `whoami.cloudflare`
+    ChaosTxt,
+}
+
+impl DnsRecord {
+    fn question(self, version: IpVersion) -> (u16, u16) {
+        match (self, version) {
+            (Self::Address, IpVersion::V4) => (TYPE_A, CLASS_IN),
+            (Self::Address, IpVersion::V6) => (TYPE_AAAA, CLASS_IN),
+            (Self::Txt, _) => (TYPE_TXT, CLASS_IN),
+            (Self::ChaosTxt, _) => (TYPE_TXT, CLASS_CH),
+        }
+    }
+}
+
+/// DNS lookup serde representation
+#[derive(Clone, Debug, Deserialize)]
+pub struct DnsLookup {
+    pub(crate) server: String,
+    pub(crate) name: String,
+    pub(crate) record: DnsRecord,
+}
+
+/// Fetches the IP address by querying each lookup's DNS server.
+pub async fn fetch_ip_dns(
+    lookups: &[DnsLookup],
+    threshold: usize,
+    version: IpVersion,
+    timeout: Duration,
+) -> Result<IpAddr> {
+    let mut set = JoinSet::new();
+    for lookup in lookups {
+        let lookup = lookup.clone();
+        set.spawn(async move {
+            let DnsLookup { server, name, .. } = &lookup;
+            time::timeout(timeout, query(&lookup, version))
+                .await
+                .with_context(|| format!("DNS lookup of {name} via {server} timed out"))?
+                .map_err(|error| anyhow!("DNS lookup of {name} via {server} failed: {error:#}"))
+        });
+    }
+
+    collect_ip_quorum(set, threshold, version, "DNS", "lookups").await
+}
+
+/// Checks a lookup's server and query name.
+pub(crate) fn validate_lookup(lookup: &DnsLookup) -> Result<()> {
+    split_host_port(&lookup.server, DEFAULT_PORT, "DNS")?;
+    if lookup.name.trim_end_matches('.').is_empty() {
+        return Err(anyhow!("DNS lookup names must not be empty"));
+    }
+    push_name(&mut Vec::new(), &lookup.name)
+}
+
+async fn query(lookup: &DnsLookup, version: IpVersion) -> Result<IpAddr> {
+    let address = resolve_host_port(&lookup.server, DEFAULT_PORT, "DNS", version).await?;
+    let socket = connect_udp(address).await?;
+
+    let id = random_id()?;
+    let (record_type, class) = lookup.record.question(version);
+    let message = build_query(id, &lookup.name, record_type, class)?;
+    let mut buf = vec![0; MAX_UDP_MESSAGE_BYTES];
+    let mut rto = INITIAL_RTO;
+    // The caller's timeout bounds the retransmissions
+    loop {
+        socket.send(&message).await?;
+        if let Ok(response) = time::timeout(rto, receive(&socket, &mut buf, id)).await {
+            let answers = parse_answers(response?, record_type, class)?;
+            return answer_ip(&answers, lookup.record, version);
+        }
+        rto *= 2;
+    }
+}
+
+async fn receive<'a>(socket: &UdpSocket, buf: &'a mut [u8], id: u16) -> Result<&'a [u8]> {
+    loop {
+        let len = socket.recv(buf).await?;
+        // Ignore stray datagrams that don't belong to this request
+        if len >= HEADER_LEN && read_u16(buf, 0)? == id {
+            return Ok(&buf[..len]);
+        }
+    }
+}
+
+fn random_id() -> Result<u16> {
+    let mut id = [0; 2];
+    SystemRandom::new()
+        .fill(&mut id)
+        .map_err(|_| anyhow!("failed to generate DNS message ID"))?;
+    Ok(u16::from_be_bytes(id))
+}
+
+fn build_query(id: u16, name: &str, record_type: u16, class: u16) -> Result<Vec<u8>> {
+    let mut message = Vec::with_capacity(HEADER_LEN + name.len() + 6);
+    push_u16(&mut message, id);
+    push_u16(&mut message, FLAG_RECURSION_DESIRED);
+    push_u16(&mut message, 1);
+    push_u16(&mut message, 0);
+    push_u16(&mut message, 0);
+    push_u16(&mut message, 0);
+    push_name(&mut message, name)?;
+    push_u16(&mut message, record_type);
+    push_u16(&mut message, class);
+    Ok(message)
+}
+
+/// Returns the data of answers matching the queried type and class.
+fn parse_answers(bytes: &[u8], record_type: u16, class: u16) -> Result<Vec<&[u8]>> {
+    let flags = read_u16(bytes, 2)?;
+    if flags & FLAG_RESPONSE == 0 {
+        return Err(anyhow!("DNS server sent a query instead of a response"));
+    }
+    if flags & FLAG_TRUNCATED != 0 {
+        return Err(anyhow!("DNS response was truncated"));
+    }
+    let rcode = flags & 0x000f;
+    if rcode != 0 {
+        return Err(anyhow!("DNS server returned {}", rcode_name(rcode)));
+    }
+    let questions = read_u16(bytes, 4)?;
+    let answers = read_u16(bytes, 6)?;
+
+    let mut offset = HEADER_LEN;
+    for _ in 0..questions {
+        offset = skip_name(bytes, offset)? + 4;
+    }
+
+    let mut data = Vec::new();
+    for _ in 0..answers {
+        let next = skip_name(bytes, offset)?;
+        let answer_type = read_u16(bytes, next)?;
+        let answer_class = read_u16(bytes, next + 2)?;
+        let len = usize::from(read_u16(bytes, next + 8)?);
+        let rdata = bytes
+            .get(next + 10..next + 10 + len)
+            .ok_or_else(|| anyhow!("DNS record data exceeded message length"))?;
+        // Answers may include a CNAME chain ahead of the queried record
+        if answer_type == record_type && answer_class == class {
+            data.push(rdata);
+        }
+        offset = next + 10 + len;
+    }
+    Ok(data)
+}
+
+/// Picks the address for `version` out of the answer record data.
+fn answer_ip(answers: &[&[u8]], record: DnsRecord, version: IpVersion) -> Result<IpAddr> {
+    if record == DnsRecord::Address {
+        let rdata = answers
+            .first()
+            .ok_or_else(|| anyhow!("DNS response has no {version} address record"))?;
+        return match (version, rdata.len()) {
+            (IpVersion::V4, 4) => Ok(IpAddr::V4(Ipv4Addr::new(
+                rdata[0], rdata[1], rdata[2], rdata[3],
+            ))),
+            (IpVersion::V6, 16) => {
+                let octets: [u8; 16] = (*rdata).try_into()?;
+                Ok(IpAddr::V6(Ipv6Addr::from(octets)))
+            }
+            (_, len) => Err(anyhow!("DNS address record has invalid length {len}")),
+        };
+    }
+
+    // Google adds an edns0-client-subnet TXT record next to the address one
+    let mut last_error = anyhow!("DNS response has no TXT record");
+    for rdata in answers {
+        match read_txt(rdata).and_then(|text| parse_ip_for_version(version, &text)) {
+            Ok(ip) => return Ok(ip),
+            Err(error) => last_error = error,
+        }
+    }
+    Err(last_error)
+}
+
+/// Joins the character-strings of a TXT record.
+fn read_txt(rdata: &[u8]) -> Result<String> {
+    let mut text = String::new();
+    let mut offset = 0;
+    while offset < rdata.len() {
+        let len = usize::from(rdata[offset]);
+        let chunk = rdata
+            .get(offset + 1..offset + 1 + len)
+            .ok_or_else(|| anyhow!("DNS TXT record was truncated"))?;
+        text.push_str(std::str::from_utf8(chunk).context("DNS TXT record was not valid UTF-8")?);
+        offset += 1 + len;
+    }
+    Ok(text)
+}
+
+/// Returns the offset just past the name starting at `offset`.
+fn skip_name(bytes: &[u8], mut offset: usize) -> Result<usize> {
+    loop {
+        let len = *bytes
+            .get(offset)
+            .ok_or_else(|| anyhow!("DNS name exceeded message length"))?;
+        match len {
+            0 => return Ok(offset + 1),
+            // A compression pointer always ends the name
+            len if len & 0xc0 == 0xc0 => return Ok(offset + 2),
+            len if len & 0xc0 == 0 => offset += 1 + usize::from(len),
+            _ => return Err(anyhow!("unsupported DNS label type")),
+        }
+    }
+}
+
+fn rcode_name(rcode: u16) -> String {
+    match rcode {
+        1 => "FORMERR".to_string(),
+        2 => "SERVFAIL".to_string(),
+        3 => "NXDOMAIN".to_string(),
+        4 => "NOTIMP".to_string(),
+        5 => "REFUSED".to_string(),
+        rcode => format!("RCODE {rcode}"),
+    }
+}
+
+fn push_name(buf: &mut Vec<u8>, name: &str) -> Result<()> {
+    let start = buf.len();
+    let trimmed = name.trim_end_matches('.');
+    if !trimmed.is_empty() {
+        for label in trimmed.split('.') {
+            let len = u8::try_from(label.len())
+                .ok()
+                .filter(|len| (1..=MAX_LABEL_BYTES).contains(&usize::from(*len)))
+                .ok_or_else(|| anyhow!("invalid DNS label in name ({name})"))?;
+            buf.push(len);
+            buf.extend_from_slice(label.as_bytes());
+        }
+    }
+    buf.push(0);
+    if buf.len() - start > MAX_NAME_BYTES {
+        return Err(anyhow!("DNS name ({name}) exceeded {MAX_NAME_BYTES} bytes"));
+    }
+    Ok(())
+}
+
+fn push_u16(buf: &mut Vec<u8>, value: u16) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| anyhow!("DNS message truncated"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    use std::time::Duration;
+
+    use tokio::net::UdpSocket;
+    use tokio::task::JoinHandle;
+
+    use crate::ip::IpVersion;
+
+    use super::*;
+
+    /// What a test DNS server does with each query it receives
+    #[derive(Clone, Copy)]
+    enum Reply {
+        Address(IpAddr),
+        Txt(&'static [&'static str]),
+        Rcode(u16),
+        Drop,
+    }
+
+    fn response(query: &[u8], rcode: u16, answers: &[Vec<u8>]) -> Vec<u8> {
+        let question_end = skip_name(query, HEADER_LEN).unwrap() + 4;
+        let record_type = read_u16(query, question_end - 4).unwrap();
+        let class = read_u16(query, question_end - 2).unwrap();
+
+        let mut message = Vec::new();
+        message.extend_from_slice(&query[..2]);
+        push_u16(&mut message, FLAG_RESPONSE | rcode);
+        push_u16(&mut message, 1);
+        push_u16(&mut message, u16::try_from(answers.len()).unwrap());
+        push_u16(&mut message, 0);
+        push_u16(&mut message, 0);
+        message.extend_from_slice(&query[HEADER_LEN..question_end]);
+        for rdata in answers {
+            // Compression pointer back to the question name
+            push_u16(&mut message, 0xc000 | u16::try_from(HEADER_LEN).unwrap());
+            push_u16(&mut message, record_type);
+            push_u16(&mut message, class);
+            message.extend_from_slice(&0u32.to_be_bytes());
+            push_u16(&mut message, u16::try_from(rdata.len()).unwrap());
+            message.extend_from_slice(rdata);
+        }
+        message
+    }
+
+    fn txt(text: &str) -> Vec<u8> {
+        let mut rdata = vec![u8::try_from(text.len()).unwrap()];
+        rdata.extend_from_slice(text.as_bytes());
+        rdata
+    }
+
+    /// Local DNS responder answering queries in order with `replies`, returning
+    /// the type and class of each query it received
+    async fn dns_server(replies: Vec<Reply>) -> (String, JoinHandle<Vec<(u16, u16)>>) {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let address = socket.local_addr().unwrap();
+        let handle = tokio::spawn(async move {
+            let mut questions = Vec::new();
+            let mut buf = vec![0; MAX_UDP_MESSAGE_BYTES];
+            for reply in replies {
+                let (len, peer) = socket.recv_from(&mut buf).await.unwrap();
+                let query = &buf[..len];
+                let question_end = skip_name(query, HEADER_LEN).unwrap();
+                questions.push((
+                    read_u16(query, question_end).unwrap(),
+                    read_u16(query, question_end + 2).unwrap(),
+                ));
+                let message = match reply {
+                    Reply::Address(IpAddr::V4(ip)) => response(query, 0, &[ip.octets().to_vec()]),
+                    Reply::Address(IpAddr::V6(ip)) => response(query, 0, &[ip.octets().to_vec()]),
+                    Reply::Txt(texts) => {
+                        let answers: Vec<_> = texts.iter().map(|text| txt(text)).collect();
+                        response(query, 0, &answers)
+                    }
+                    Reply::Rcode(rcode) => response(query, rcode, &[]),
+                    Reply::Drop => continue,
+                };
+                socket.send_to(&message, peer).await.unwrap();
+            }
+            questions
+        });
+        (address.to_string(), handle)
+    }
+
+    fn lookup(server: String, name: &str, record: DnsRecord) -> DnsLookup {
+        DnsLookup {
+            server,
+            name: name.to_string(),
+            record,
+        }
+    }
+
+    const TIMEOUT: Duration = Duration::from_secs(3);
+
+    #[tokio::test]
+    async fn fetch_ip_dns_reaches_quorum_across_record_kinds() {
+        let ip = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10));
+        let (opendns, opendns_questions) = dns_server(vec![Reply::Address(ip)]).await;
+        let (google, google_questions) = dns_server(vec![Reply::Txt(&["192.0.2.10"])]).await;
+        let (cloudflare, cloudflare_questions) =
+            dns_server(vec![Reply::Txt(&["192.0.2.10"])]).await;
+        let lookups = vec![
+            lookup(opendns, "myip.opendns.com", DnsRecord::Address),
+            lookup(google, "o-o.myaddr.l.google.com", DnsRecord::Txt),
+            lookup(cloudflare, "whoami.cloudflare", DnsRecord::ChaosTxt),
+        ];
+
+        let result = fetch_ip_dns(&lookups, 3, IpVersion::V4, TIMEOUT)
+            .await
+            .unwrap();
+
+        assert_eq!(result, ip);
+        assert_eq!(opendns_questions.await.unwrap(), [(TYPE_A, CLASS_IN)]);
+        assert_eq!(google_questions.await.unwrap(), [(TYPE_TXT, CLASS_IN)]);
+        assert_eq!(cloudflare_questions.await.unwrap(), [(TYPE_TXT, CLASS_CH)]);
+    }
+
+    #[tokio::test]
+    async fn parses_aaaa_answers_for_ipv6() {
+        let ip = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+        let (server, questions) = dns_server(vec![Reply::Address(ip)]).await;
+        // The stand-in listens on IPv4 loopback, so exchange the AAAA query
+        // directly instead of resolving an IPv6 server address
+        let socket = connect_udp(server.parse().unwrap()).await.unwrap();
+        let message = build_query(7, "myip.opendns.com", TYPE_AAAA, CLASS_IN).unwrap();
+        socket.send(&message).await.unwrap();
+        let mut buf = vec![0; MAX_UDP_MESSAGE_BYTES];
+        let response = receive(&socket, &mut buf, 7).await.unwrap();
+        let answers = parse_answers(response, TYPE_AAAA, CLASS_IN).unwrap();
+
+        assert_eq!(
+            answer_ip(&answers, DnsRecord::Address, IpVersion::V6).unwrap(),
+            ip
+        );
+        assert_eq!(questions.await.unwrap(), [(TYPE_AAAA, CLASS_IN)]);
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_dns_retransmits_lost_queries() {
+        let ip = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10));
+        let (server, _) = dns_server(vec![Reply::Drop, Reply::Address(ip)]).await;
+        let lookups = vec![lookup(server, "myip.opendns.com", DnsRecord::Address)];
+
+        let result = fetch_ip_dns(&lookups, 1, IpVersion::V4, TIMEOUT)
+            .await
+            .unwrap();
+
+        assert_eq!(result, ip);
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_dns_reports_error_responses() {
+        let ip = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10));
+        let (refused, _) = dns_server(vec![Reply::Rcode(5)]).await;
+        let (answered, _) = dns_server(vec![Reply::Address(ip)]).await;
+        let lookups = vec![
+            lookup(refused.clone(), "myip.opendns.com", DnsRecord::Address),
+            lookup(answered, "myip.opendns.com", DnsRecord::Address),
+        ];
+
+        let error = fetch_ip_dns(&lookups, 2, IpVersion::V4, TIMEOUT)
+            .await
+            .unwrap_err();
+        let message = error.to_string();
+
+        assert!(
+            message.contains(
+                "no DNS IP lookup quorum reached for IPv4: required 2 matching responses from 2 lookups"
+            ),
+            "{message}"
+        );
+        assert!(message.contains("192.0.2.10=1"), "{message}");
+        assert!(
+            message.contains(&format!(
+                "DNS lookup of myip.opendns.com via {refused} failed: DNS server returned REFUSED"
+            )),
+            "{message}"
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_dns_skips_txt_records_that_are_not_addresses() {
+        let (server, _) = dns_server(vec![Reply::Txt(&[
+            "edns0-client-subnet 198.51.100.0/24",
+            "192.0.2.10",
+        ])])
+        .await;
+        let lookups = vec![lookup(server, "o-o.myaddr.l.google.com", DnsRecord::Txt)];
+
+        let result = fetch_ip_dns(&lookups, 1, IpVersion::V4, TIMEOUT)
+            .await
+            .unwrap();
+
+        assert_eq!(result, IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10)));
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_dns_rejects_txt_address_of_other_version() {
+        let (server, _) = dns_server(vec![Reply::Txt(&["2001:db8::1"])]).await;
+        let lookups = vec![lookup(server, "whoami.cloudflare", DnsRecord::ChaosTxt)];
+
+        let error = fetch_ip_dns(&lookups, 1, IpVersion::V4, TIMEOUT)
+            .await
+            .unwrap_err();
+
+        assert!(
+            error.to_string().contains("expected IPv4 address"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_responses() {
+        let query = build_query(1, "myip.opendns.com", TYPE_A, CLASS_IN).unwrap();
+        let mut message = response(&query, 0, &[vec![192, 0, 2, 10]]);
+        message[2] |= 0x02;
+
+        assert!(
+            parse_answers(&message, TYPE_A, CLASS_IN)
+                .unwrap_err()
+                .to_string()
+                .contains("DNS response was truncated")
+        );
+    }
+
+    #[test]
+    fn validates_lookups() {
+        let valid = lookup(
+            "resolver1.opendns.com".to_string(),
+            "myip.opendns.com",
+            DnsRecord::Address,
+        );
+        let empty_name = lookup("127.0.0.1".to_string(), ".", DnsRecord::Txt);
+        let long_label = lookup("127.0.0.1".to_string(), &"a".repeat(64), DnsRecord::Txt);
+        let bad_port = lookup("127.0.0.1:dns".to_string(), "whoami", DnsRecord::Txt);
+
+        assert!(validate_lookup(&valid).is_ok());
+        assert!(
+            validate_lookup(&empty_name)
+                .unwrap_err()
+                .to_string()
+                .contains("DNS lookup names must not be empty")
+        );
+        assert!(
+            validate_lookup(&long_label)
+                .unwrap_err()
+                .to_string()
+                .contains("invalid DNS label")
+        );
+        assert!(
+            validate_lookup(&bad_port)
+                .unwrap_err()
+                .to_string()
+                .contains("invalid DNS server 127.0.0.1:dns: invalid port")
+        );
+    }
+}
@@ -0,0 +1,868 @@
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use reqwest::StatusCode;
+use reqwest::header::CONTENT_TYPE;
+use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::Deserialize;
+use tokio::fs;
+use tokio::net::UdpSocket;
+use tokio::time;
+use tracing::debug;
+use url::Url;
+
+use super::connect_udp;
+
+const MAX_MESSAGE_BYTES: usize = 1500;
+
+const SSDP_MULTICAST_ADDRESS: Ipv4Addr = Ipv4Addr::new(239, 255, 255, 250);
+const SSDP_PORT: u16 = 1900;
+const SSDP_INITIAL_RTO: Duration = Duration::from_secs(1);
+// IGD:2 devices also answer searches for IGD:1
+const IGD_DEVICE_TYPE: &str = "urn:schemas-upnp-org:device:InternetGatewayDevice:1";
+const WAN_CONNECTION_SERVICES: [&str; 2] = [
+    "urn:schemas-upnp-org:service:WANIPConnection:",
+    "urn:schemas-upnp-org:service:WANPPPConnection:",
+];
+
+// NAT-PMP and PCP share the same gateway port
+const PORT_MAPPING_PORT: u16 = 5351;
+// RFC 6886 section 3.1 starts retransmissions at 250ms
+const NAT_PMP_INITIAL_RTO: Duration = Duration::from_millis(250);
+const NAT_PMP_VERSION: u8 = 0;
+const NAT_PMP_EXTERNAL_ADDRESS: u8 = 0;
+const NAT_PMP_RESPONSE_LEN: usize = 12;
+
+// RFC 6887 section 8.1.1 starts retransmissions at 3s
+const PCP_INITIAL_RTO: Duration = Duration::from_secs(3);
+const PCP_VERSION: u8 = 2;
+const PCP_OPCODE_MAP: u8 = 1;
+const PCP_RESPONSE: u8 = 0x80;
+const PCP_HEADER_LEN: usize = 24;
+const PCP_MAP_LEN: usize = PCP_HEADER_LEN + 36;
+const PCP_PROTOCOL_UDP: u8 = 17;
+const PCP_MAPPING_LIFETIME: u32 = 60;
+
+type Nonce = [u8; 12];
+
+/// Protocol used to ask the router for its WAN address
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RouterProtocol {
+    Upnp,
+    NatPmp,
+    Pcp,
+}
+
+impl fmt::Display for RouterProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Upnp => write!(f, "UPnP"),
+            Self::NatPmp => write!(f, "NAT-PMP"),
+            Self::Pcp => write!(f, "PCP"),
+        }
+    }
+}
+
+/// Fetches the router's external IPv4 address, trying each protocol in order.
+pub async fn fetch_ip_router(
+    request: &HttpClient,
+    protocols: &[RouterProtocol],
+    gateway: Option<Ipv4Addr>,
+    timeout: Duration,
+) -> Result<IpAddr> {
+    // Without a gateway, the IGD is discovered by multicast
+    let ssdp = SocketAddr::from((gateway.unwrap_or(SSDP_MULTICAST_ADDRESS), SSDP_PORT));
+    let gateway = gateway.map(|gateway| SocketAddr::from((gateway, PORT_MAPPING_PORT)));
+    fetch_ip(request, protocols, ssdp, gateway, timeout).await
+}
+
+async fn fetch_ip(
+    request: &HttpClient,
+    protocols: &[RouterProtocol],
+    ssdp: SocketAddr,
+    gateway: Option<SocketAddr>,
+    timeout: Duration,
+) -> Result<IpAddr> {
+    let mut failures = Vec::new();
+    for &protocol in protocols {
+        let lookup = async {
+            match protocol {
+                RouterProtocol::Upnp => upnp_external_ip(request, ssdp).await,
+                RouterProtocol::NatPmp => {
+                    nat_pmp_external_ip(port_mapping_gateway(gateway).await?).await
+                }
+                RouterProtocol::Pcp => pcp_external_ip(port_mapping_gateway(gateway).await?).await,
+            }
+        };
+        let result = time::timeout(timeout, lookup)
+            .await
+            .unwrap_or_else(|_| Err(anyhow!("request timed out")));
+        match result {
+            Ok(ip) => {
+                debug!("router returned external IP address {ip} via {protocol}");
+                return Ok(IpAddr::V4(ip));
+            }
+            Err(error) => {
+                debug!("router IP lookup via {protocol} failed: {error:#}");
+                failures.push(format!("{protocol}: {error:#}"));
+            }
+        }
+    }
+
+    Err(anyhow!(
+        "no router protocol returned an external IP address [{}]",
+        failures.join("; ")
+    ))
+}
+
+async fn port_mapping_gateway(gateway: Option<SocketAddr>) -> Result<SocketAddr> {
+    if let Some(gateway) = gateway {
+        return Ok(gateway);
+    }
+    let table = fs::read_to_string("/proc/net/route")
+        .await
+        .context("failed to read the routing table, set source.gateway")?;
+    parse_default_gateway(&table)
+        .map(|gateway| SocketAddr::from((gateway, PORT_MAPPING_PORT)))
+        .ok_or_else(|| anyhow!("no IPv4 default gateway found, set source.gateway"))
+}
+
+/// Finds the default route's gateway in a Linux `/proc/net/route` table.
+fn parse_default_gateway(table: &str) -> Option<Ipv4Addr> {
+    const RTF_UP: u16 = 0x0001;
+    const RTF_GATEWAY: u16 = 0x0002;
+
+    table.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (destination, gateway, flags) = (fields.get(1)?, fields.get(2)?, fields.get(3)?);
+        let flags = u16::from_str_radix(flags, 16).ok()?;
+        if *destination != "00000000" || flags & (RTF_UP | RTF_GATEWAY) != RTF_UP | RTF_GATEWAY {
+            return None;
+        }
+        // Addresses are printed as native-endian words holding network-order bytes
+        let gateway = u32::from_str_radix(gateway, 16).ok()?;
+        Some(Ipv4Addr::from(gateway.to_ne_bytes()))
+    })
+}
+
+async fn upnp_external_ip(request: &HttpClient, ssdp: SocketAddr) -> Result<Ipv4Addr> {
+    let location = ssdp_search(ssdp).await?;
+    debug!("found UPnP IGD at {location}");
+    let (service_type, control_url) = find_wan_connection(request, &location).await?;
+    get_external_ip_address(request, &service_type, &control_url).await
+}
+
+async fn ssdp_search(target: SocketAddr) -> Result<Url> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    let message = format!(
+        "M-SEARCH * HTTP/1.1\r\nHOST: {SSDP_MULTICAST_ADDRESS}:{SSDP_PORT}\r\nMAN: \"ssdp:discover\"\r\nMX: 2\r\nST: {IGD_DEVICE_TYPE}\r\n\r\n"
+    );
+    let mut buf = vec![0; MAX_MESSAGE_BYTES];
+    let mut rto = SSDP_INITIAL_RTO;
+    // The caller's timeout bounds the retransmissions
+    loop {
+        socket.send_to(message.as_bytes(), target).await?;
+        if let Ok(location) = time::timeout(rto, receive_location(&socket, &mut buf)).await {
+            return location;
+        }
+        rto *= 2;
+    }
+}
+
+async fn receive_location(socket: &UdpSocket, buf: &mut [u8]) -> Result<Url> {
+    loop {
+        let (len, _) = socket.recv_from(buf).await?;
+        // Other devices on the network may answer without a usable location
+        if let Some(location) = parse_ssdp_response(&buf[..len]) {
+            return Ok(location);
+        }
+    }
+}
+
+fn parse_ssdp_response(message: &[u8]) -> Option<Url> {
+    let message = std::str::from_utf8(message).ok()?;
+    let mut lines = message.lines();
+    if !lines.next()?.starts_with("HTTP/1.1 200") {
+        return None;
+    }
+    lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("location"))
+        .and_then(|(_, value)| Url::parse(value.trim()).ok())
+}
+
+async fn find_wan_connection(request: &HttpClient, location: &Url) -> Result<(String, Url)> {
+    let response = request
+        .get(location.as_str())
+        .send()
+        .await
+        .with_context(|| format!("failed to fetch UPnP device description from {location}"))?;
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    if !status.is_success() {
+        let detail = body_snippet(&body)
+            .map(|snippet| format!(": {snippet}"))
+            .unwrap_or_default();
+        return Err(anyhow!(
+            "failed to fetch UPnP device description: HTTP {status}{detail}"
+        ));
+    }
+    parse_device_description(&body, location)
+}
+
+/// Returns the service type and control URL of the first WAN connection
+/// service in a device description.
+fn parse_device_description(body: &str, location: &Url) -> Result<(String, Url)> {
+    let document =
+        roxmltree::Document::parse(body).context("failed to parse UPnP device description")?;
+    let base = document
+        .descendants()
+        .find(|node| node.has_tag_name("URLBase"))
+        .and_then(|node| node.text())
+        .and_then(|base| Url::parse(base.trim()).ok())
+        .unwrap_or_else(|| location.clone());
+
+    for service in document
+        .descendants()
+        .filter(|node| node.has_tag_name("service"))
+    {
+        let child_text = |name| {
+            service
+                .children()
+                .find(|child| child.has_tag_name(name))
+                .and_then(|child| child.text())
+                .map(str::trim)
+        };
+        let Some(service_type) = child_text("serviceType") else {
+            continue;
+        };
+        if !WAN_CONNECTION_SERVICES
+            .iter()
+            .any(|prefix| service_type.starts_with(prefix))
+        {
+            continue;
+        }
+        let control_url = child_text("controlURL")
+            .ok_or_else(|| anyhow!("UPnP service {service_type} has no controlURL"))?;
+        let control_url = base
+            .join(control_url)
+            .with_context(|| format!("invalid UPnP controlURL ({control_url})"))?;
+        return Ok((service_type.to_string(), control_url));
+    }
+
+    Err(anyhow!(
+        "UPnP device has no WANIPConnection or WANPPPConnection service"
+    ))
+}
+
+async fn get_external_ip_address(
+    request: &HttpClient,
+    service_type: &str,
+    control_url: &Url,
+) -> Result<Ipv4Addr> {
+    let body = format!(
+        r#"<?xml version="1.0"?><s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/"><s:Body><u:GetExternalIPAddress xmlns:u="{service_type}"></u:GetExternalIPAddress></s:Body></s:Envelope>"#
+    );
+    let response = request
+        .post(control_url.as_str())
+        .header(CONTENT_TYPE, r#"text/xml; charset="utf-8""#)
+        .header(
+            "SOAPAction",
+            format!(r#""{service_type}#GetExternalIPAddress""#),
+        )
+        .body(body)
+        .send()
+        .await
+        .with_context(|| format!("UPnP request failed for {control_url}"))?;
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    parse_external_ip_response(status, &body)
+}
+
+fn parse_external_ip_response(status: StatusCode, body: &str) -> Result<Ipv4Addr> {
+    let action = "get UPnP external IP address";
+    let document = roxmltree::Document::parse(body);
+    if !status.is_success() {
+        // SOAP faults carry the UPnP errorCode and errorDescription
+        let detail = document
+            .ok()
+            .and_then(|document| upnp_error(&document))
+            .or_else(|| body_snippet(body))
+            .map(|detail| format!(": {detail}"))
+            .unwrap_or_default();
+        return Err(anyhow!("failed to {action}: HTTP {status}{detail}"));
+    }
+
+    let document = document.with_context(|| format!("failed to {action}: invalid XML"))?;
+    let address = document
+        .descendants()
+        .find(|node| node.has_tag_name("NewExternalIPAddress"))
+        .ok_or_else(|| anyhow!("failed to {action}: missing NewExternalIPAddress"))?
+        .text()
+        .unwrap_or_default()
+        .trim();
+    if address.is_empty() {
+        return Err(anyhow!(
+            "router has no external IP address, the WAN connection may be down"
+        ));
+    }
+    address
+        .parse()
+        .map_err(|_| anyhow!("router returned an invalid external IP address ({address})"))
+}
+
+fn upnp_error(document: &roxmltree::Document) -> Option<String> {
+    let text = |name| {
+        document
+            .descendants()
+            .find(|node| node.has_tag_name(name))
+            .and_then(|node| node.text())
+            .map(str::trim)
+    };
+    let code = text("errorCode")?;
+    Some(match text("errorDescription") {
+        Some(description) => format!("UPnP error {code}: {description}"),
+        None => format!("UPnP error {code}"),
+    })
+}
+
+async fn nat_pmp_external_ip(gateway: SocketAddr) -> Result<Ipv4Addr> {
+    let socket = connect_udp(gateway).await?;
+    let request = [NAT_PMP_VERSION, NAT_PMP_EXTERNAL_ADDRESS];
+    let mut buf = vec![0; MAX_MESSAGE_BYTES];
+    let mut rto = NAT_PMP_INITIAL_RTO;
+    // The caller's timeout bounds the retransmissions
+    loop {
+        socket.send(&request).await?;
+        let receive = receive_opcode(&socket, &mut buf, PCP_RESPONSE | NAT_PMP_EXTERNAL_ADDRESS);
+        if let Ok(response) = time::timeout(rto, receive).await {
+            return parse_nat_pmp_response(response?);
+        }
+        rto *= 2;
+    }
+}
+
+async fn receive_opcode<'a>(socket: &UdpSocket, buf: &'a mut [u8], opcode: u8) -> Result<&'a [u8]> {
+    loop {
+        let len = socket.recv(buf).await?;
+        // Ignore stray datagrams such as unsolicited address announcements
+        if len >= 4 && buf[1] == opcode {
+            return Ok(&buf[..len]);
+        }
+    }
+}
+
+fn parse_nat_pmp_response(message: &[u8]) -> Result<Ipv4Addr> {
+    let result = read_u16(message, 2)?;
+    if result != 0 {
+        return Err(anyhow!(
+            "NAT-PMP gateway returned {}",
+            nat_pmp_result_name(result)
+        ));
+    }
+    let address: [u8; 4] = message
+        .get(8..NAT_PMP_RESPONSE_LEN)
+        .and_then(|address| address.try_into().ok())
+        .ok_or_else(|| anyhow!("NAT-PMP response truncated"))?;
+    let address = Ipv4Addr::from(address);
+    if address.is_unspecified() {
+        return Err(anyhow!("NAT-PMP gateway has no external IP address"));
+    }
+    Ok(address)
+}
+
+fn nat_pmp_result_name(result: u16) -> String {
+    match result {
+        1 => "Unsupported Version".to_string(),
+        2 => "Not Authorized/Refused".to_string(),
+        3 => "Network Failure".to_string(),
+        4 => "Out of resources".to_string(),
+        5 => "Unsupported opcode".to_string(),
+        result => format!("result code {result}"),
+    }
+}
+
+/// Requests a short-lived UDP mapping, since PCP only reports the external
+/// address as part of a mapping, then deletes it again.
+async fn pcp_external_ip(gateway: SocketAddr) -> Result<Ipv4Addr> {
+    let socket = connect_udp(gateway).await?;
+    let client = socket.local_addr()?;
+    let nonce = nonce()?;
+    let request = pcp_map_request(client, &nonce, PCP_MAPPING_LIFETIME);
+    let mut buf = vec![0; MAX_MESSAGE_BYTES];
+    let mut rto = PCP_INITIAL_RTO;
+    // The caller's timeout bounds the retransmissions
+    loop {
+        socket.send(&request).await?;
+        let receive = receive_opcode(&socket, &mut buf, PCP_RESPONSE | PCP_OPCODE_MAP);
+        if let Ok(response) = time::timeout(rto, receive).await {
+            let address = parse_pcp_response(response?, &nonce)?;
+            let delete = pcp_map_request(client, &nonce, 0);
+            if let Err(error) = socket.send(&delete).await {
+                debug!("failed to delete PCP mapping: {error}");
+            }
+            return Ok(address);
+        }
+        rto *= 2;
+    }
+}
+
+fn nonce() -> Result<Nonce> {
+    let mut nonce = [0; 12];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| anyhow!("failed to generate PCP mapping nonce"))?;
+    Ok(nonce)
+}
+
+fn pcp_map_request(client: SocketAddr, nonce: &Nonce, lifetime: u32) -> Vec<u8> {
+    let client_ip = match client.ip() {
+        IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+        IpAddr::V6(ip) => ip,
+    };
+    let mut message = Vec::with_capacity(PCP_MAP_LEN);
+    message.extend_from_slice(&[PCP_VERSION, PCP_OPCODE_MAP, 0, 0]);
+    message.extend_from_slice(&lifetime.to_be_bytes());
+    message.extend_from_slice(&client_ip.octets());
+    message.extend_from_slice(nonce);
+    message.extend_from_slice(&[PCP_PROTOCOL_UDP, 0, 0, 0]);
+    message.extend_from_slice(&client.port().to_be_bytes());
+    // Any external port and IPv4 address
+    message.extend_from_slice(&0u16.to_be_bytes());
+    message.extend_from_slice(&Ipv4Addr::UNSPECIFIED.to_ipv6_mapped().octets());
+    message
+}
+
+fn parse_pcp_response(message: &[u8], nonce: &Nonce) -> Result<Ipv4Addr> {
+    if message[0] != PCP_VERSION {
+        return Err(anyhow!(
+            "PCP gateway responded with version {}, expected {PCP_VERSION}",
+            message[0]
+        ));
+    }
+    let result = message[3];
+    if result != 0 {
+        return Err(anyhow!("PCP gateway returned {}", pcp_result_name(result)));
+    }
+    if message.len() < PCP_MAP_LEN {
+        return Err(anyhow!("PCP response truncated"));
+    }
+    if message[PCP_HEADER_LEN..PCP_HEADER_LEN + 12] != nonce[..] {
+        return Err(anyhow!("PCP response nonce mismatch"));
+    }
+    let address: [u8; 16] = message[PCP_MAP_LEN - 16..PCP_MAP_LEN].try_into()?;
+    let address = Ipv6Addr::from(address);
+    address
+        .to_ipv4_mapped()
+        .ok_or_else(|| anyhow!("PCP gateway returned {address}, expected an IPv4 address"))
+}
+
+fn pcp_result_name(result: u8) -> String {
+    match result {
+        1 => "UNSUPP_VERSION".to_string(),
+        2 => "NOT_AUTHORIZED".to_string(),
+        3 => "MALFORMED_REQUEST".to_string(),
+        4 => "UNSUPP_OPCODE".to_string(),
+        5 => "UNSUPP_OPTION".to_string(),
+        6 => "MALFORMED_OPTION".to_string(),
+        7 => "NETWORK_FAILURE".to_string(),
+        8 => "NO_RESOURCES".to_string(),
+        9 => "UNSUPP_PROTOCOL".to_string(),
+        10 => "USER_EX_QUOTA".to_string(),
+        11 => "CANNOT_PROVIDE_EXTERNAL".to_string(),
+        12 => "ADDRESS_MISMATCH".to_string(),
+        13 => "EXCESSIVE_REMOTE_PEERS".to_string(),
+        result => format!("result code {result}"),
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| anyhow!("router response truncated"))
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+    use std::time::Duration;
+
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use tokio::net::UdpSocket;
+    use tokio::task::JoinHandle;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(3);
+    const EXTERNAL_IP: Ipv4Addr = Ipv4Addr::new(203, 0, 113, 7);
+
+    const DEVICE_DESCRIPTION: &str = r#"<?xml version="1.0"?>
+<root xmlns="urn:schemas-upnp-org:device-1-0">
+  <device>
+    <deviceType>urn:schemas-upnp-org:device:InternetGatewayDevice:1</deviceType>
+    <serviceList>
+      <service>
+        <serviceType>urn:schemas-upnp-org:service:Layer3Forwarding:1</serviceType>
+        <controlURL>/ctl/L3F</controlURL>
+      </service>
+    </serviceList>
+    <deviceList>
+      <device>
+        <deviceType>urn:schemas-upnp-org:device:WANConnectionDevice:1</deviceType>
+        <serviceList>
+          <service>
+            <serviceType>urn:schemas-upnp-org:service:WANIPConnection:1</serviceType>
+            <controlURL>/ctl/IPConn</controlURL>
+          </service>
+        </serviceList>
+      </device>
+    </deviceList>
+  </device>
+</root>"#;
+
+    const EXTERNAL_IP_RESPONSE: &str = r#"<?xml version="1.0"?>
+<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
+  <s:Body>
+    <u:GetExternalIPAddressResponse xmlns:u="urn:schemas-upnp-org:service:WANIPConnection:1">
+      <NewExternalIPAddress>203.0.113.7</NewExternalIPAddress>
+    </u:GetExternalIPAddressResponse>
+  </s:Body>
+</s:Envelope>"#;
+
+    const FAULT_RESPONSE: &str = r#"<?xml version="1.0"?>
+<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/">
+  <s:Body>
+    <s:Fault>
+      <faultcode>s:Client</faultcode>
+      <faultstring>UPnPError</faultstring>
+      <detail>
+        <UPnPError xmlns="urn:schemas-upnp-org:control-1-0">
+          <errorCode>501</errorCode>
+          <errorDescription>Action Failed</errorDescription>
+        </UPnPError>
+      </detail>
+    </s:Fault>
+  </s:Body>
+</s:Envelope>"#;
+
+    /// Local UDP responder answering `requests` datagrams with `reply`,
+    /// returning the requests it received
+    async fn udp_server<F>(requests: usize, reply: F) -> (SocketAddr, JoinHandle<Vec<Vec<u8>>>)
+    where
+        F: Fn(&[u8]) -> Option<Vec<u8>> + Send + 'static,
+    {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let address = socket.local_addr().unwrap();
+        let handle = tokio::spawn(async move {
+            let mut received = Vec::new();
+            let mut buf = vec![0; MAX_MESSAGE_BYTES];
+            for _ in 0..requests {
+                let (len, peer) = socket.recv_from(&mut buf).await.unwrap();
+                received.push(buf[..len].to_vec());
+                if let Some(message) = reply(&buf[..len]) {
+                    socket.send_to(&message, peer).await.unwrap();
+                }
+            }
+            received
+        });
+        (address, handle)
+    }
+
+    async fn ssdp_server(location: String) -> (SocketAddr, JoinHandle<Vec<Vec<u8>>>) {
+        udp_server(1, move |_| {
+            Some(
+                format!(
+                    "HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age=120\r\nST: {IGD_DEVICE_TYPE}\r\nLocation: {location}\r\n\r\n"
+                )
+                .into_bytes(),
+            )
+        })
+        .await
+    }
+
+    fn nat_pmp_response(result: u16, address: Ipv4Addr) -> Vec<u8> {
+        let mut message = vec![NAT_PMP_VERSION, PCP_RESPONSE | NAT_PMP_EXTERNAL_ADDRESS];
+        message.extend_from_slice(&result.to_be_bytes());
+        message.extend_from_slice(&1000u32.to_be_bytes());
+        message.extend_from_slice(&address.octets());
+        message
+    }
+
+    fn pcp_response(request: &[u8], result: u8, address: Ipv4Addr) -> Vec<u8> {
+        let mut message = vec![PCP_VERSION, PCP_RESPONSE | PCP_OPCODE_MAP, 0, result];
+        message.extend_from_slice(&request[4..8]);
+        message.extend_from_slice(&1000u32.to_be_bytes());
+        message.extend_from_slice(&[0; 12]);
+        message.extend_from_slice(&request[PCP_HEADER_LEN..PCP_MAP_LEN - 18]);
+        message.extend_from_slice(&40000u16.to_be_bytes());
+        message.extend_from_slice(&address.to_ipv6_mapped().octets());
+        message
+    }
+
+    async fn mount_igd(mock: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path("/rootDesc.xml"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(DEVICE_DESCRIPTION))
+            .expect(1)
+            .mount(mock)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_router_uses_upnp_igd() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        mount_igd(&mock).await;
+        Mock::given(method("POST"))
+            .and(path("/ctl/IPConn"))
+            .and(header(
+                "SOAPAction",
+                r#""urn:schemas-upnp-org:service:WANIPConnection:1#GetExternalIPAddress""#,
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_string(EXTERNAL_IP_RESPONSE))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        let (ssdp, searches) = ssdp_server(format!("{}/rootDesc.xml", mock.uri())).await;
+
+        let result = fetch_ip(&http, &[RouterProtocol::Upnp], ssdp, None, TIMEOUT)
+            .await
+            .unwrap();
+
+        assert_eq!(result, IpAddr::V4(EXTERNAL_IP));
+        let searches = searches.await.unwrap();
+        let search = std::str::from_utf8(&searches[0]).unwrap();
+        assert!(search.starts_with("M-SEARCH * HTTP/1.1\r\n"), "{search}");
+        assert!(
+            search.contains(&format!("ST: {IGD_DEVICE_TYPE}\r\n")),
+            "{search}"
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_router_reports_upnp_faults() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        mount_igd(&mock).await;
+        Mock::given(method("POST"))
+            .and(path("/ctl/IPConn"))
+            .respond_with(ResponseTemplate::new(500).set_body_string(FAULT_RESPONSE))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        let (ssdp, _) = ssdp_server(format!("{}/rootDesc.xml", mock.uri())).await;
+
+        let error = fetch_ip(&http, &[RouterProtocol::Upnp], ssdp, None, TIMEOUT)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "no router protocol returned an external IP address [UPnP: failed to get UPnP external IP address: HTTP 500 Internal Server Error: UPnP error 501: Action Failed]"
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_router_uses_nat_pmp() {
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let (gateway, requests) = udp_server(1, |_| Some(nat_pmp_response(0, EXTERNAL_IP))).await;
+
+        let result = fetch_ip(
+            &http,
+            &[RouterProtocol::NatPmp],
+            gateway,
+            Some(gateway),
+            TIMEOUT,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result, IpAddr::V4(EXTERNAL_IP));
+        assert_eq!(requests.await.unwrap(), [vec![0, 0]]);
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_router_uses_pcp_and_deletes_the_mapping() {
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let (gateway, requests) =
+            udp_server(2, |request| Some(pcp_response(request, 0, EXTERNAL_IP))).await;
+
+        let result = fetch_ip(
+            &http,
+            &[RouterProtocol::Pcp],
+            gateway,
+            Some(gateway),
+            TIMEOUT,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result, IpAddr::V4(EXTERNAL_IP));
+        let requests = requests.await.unwrap();
+        let (map, delete) = (&requests[0], &requests[1]);
+        assert_eq!(map.len(), PCP_MAP_LEN);
+        assert_eq!(map[..2], [PCP_VERSION, PCP_OPCODE_MAP]);
+        assert_eq!(map[4..8], PCP_MAPPING_LIFETIME.to_be_bytes());
+        assert_eq!(
+            map[8..PCP_HEADER_LEN],
+            Ipv4Addr::LOCALHOST.to_ipv6_mapped().octets()
+        );
+        assert_eq!(map[36], PCP_PROTOCOL_UDP);
+        assert_eq!(delete[4..8], 0u32.to_be_bytes());
+        assert_eq!(delete[PCP_HEADER_LEN..], map[PCP_HEADER_LEN..]);
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_router_falls_back_to_next_protocol() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        Mock::given(method("GET"))
+            .and(path("/rootDesc.xml"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        let (ssdp, _) = ssdp_server(format!("{}/rootDesc.xml", mock.uri())).await;
+        let (gateway, _) = udp_server(2, |request| {
+            Some(match request[0] {
+                PCP_VERSION => pcp_response(request, 2, Ipv4Addr::UNSPECIFIED),
+                _ => nat_pmp_response(0, EXTERNAL_IP),
+            })
+        })
+        .await;
+
+        let result = fetch_ip(
+            &http,
+            &[
+                RouterProtocol::Upnp,
+                RouterProtocol::Pcp,
+                RouterProtocol::NatPmp,
+            ],
+            ssdp,
+            Some(gateway),
+            TIMEOUT,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result, IpAddr::V4(EXTERNAL_IP));
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_router_reports_each_protocol_failure() {
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let (gateway, _) =
+            udp_server(1, |_| Some(nat_pmp_response(2, Ipv4Addr::UNSPECIFIED))).await;
+
+        let error = fetch_ip(
+            &http,
+            &[RouterProtocol::NatPmp],
+            gateway,
+            Some(gateway),
+            TIMEOUT,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "no router protocol returned an external IP address [NAT-PMP: NAT-PMP gateway returned Not Authorized/Refused]"
+        );
+    }
+
+    #[test]
+    fn parses_device_description_with_url_base() {
+        let location = Url::parse("http://192.168.1.1:5000/rootDesc.xml").unwrap();
+        let description = DEVICE_DESCRIPTION.replace(
+            "<device>",
+            "<URLBase>http://192.168.1.1:49152/</URLBase><device>",
+        );
+
+        let (service_type, control_url) =
+            parse_device_description(&description, &location).unwrap();
+
+        assert_eq!(
+            service_type,
+            "urn:schemas-upnp-org:service:WANIPConnection:1"
+        );
+        assert_eq!(control_url.as_str(), "http://192.168.1.1:49152/ctl/IPConn");
+    }
+
+    #[test]
+    fn parses_ssdp_responses() {
+        assert_eq!(
+            parse_ssdp_response(
+                b"HTTP/1.1 200 OK\r\nlocation: http://192.168.1.1:5000/rootDesc.xml\r\n\r\n"
+            )
+            .unwrap()
+            .as_str(),
+            "http://192.168.1.1:5000/rootDesc.xml"
+        );
+        assert!(
+            parse_ssdp_response(b"NOTIFY * HTTP/1.1\r\nLOCATION: http://192.168.1.1/\r\n\r\n")
+                .is_none()
+        );
+        assert!(parse_ssdp_response(b"HTTP/1.1 200 OK\r\nST: upnp:rootdevice\r\n\r\n").is_none());
+    }
+
+    #[test]
+    fn parses_default_gateway() {
+        // The kernel prints addresses as native-endian words
+        let hex = |octets: [u8; 4]| format!("{:08X}", u32::from_ne_bytes(octets));
+        let table = format!(
+            "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n\
+            eth0\t{}\t00000000\t0001\t0\t0\t0\t{}\t0\t0\t0\n\
+            eth0\t00000000\t{}\t0003\t0\t0\t0\t00000000\t0\t0\t0\n",
+            hex([192, 168, 1, 0]),
+            hex([255, 255, 255, 0]),
+            hex([192, 168, 1, 1]),
+        );
+
+        assert_eq!(
+            parse_default_gateway(&table),
+            Some(Ipv4Addr::new(192, 168, 1, 1))
+        );
+        assert_eq!(
+            parse_default_gateway(
+                table
+                    .lines()
+                    .take(2)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+                    .as_str()
+            ),
+            None
+        );
+    }
+}
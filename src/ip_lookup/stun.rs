@@ -0,0 +1,431 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use ring::rand::{SecureRandom, SystemRandom};
+use tokio::net::UdpSocket;
+use tokio::task::JoinSet;
+use tokio::time;
+
+use crate::ip::IpVersion;
+
+use super::{collect_ip_quorum, connect_udp, resolve_host_port, split_host_port};
+
+const DEFAULT_PORT: u16 = 3478;
+const HEADER_LEN: usize = 20;
+const MAGIC_COOKIE: u32 = 0x2112_A442;
+const BINDING_REQUEST: u16 = 0x0001;
+const BINDING_SUCCESS: u16 = 0x0101;
+const BINDING_ERROR: u16 = 0x0111;
+const ATTR_MAPPED_ADDRESS: u16 = 0x0001;
+const ATTR_ERROR_CODE: u16 = 0x0009;
+const ATTR_XOR_MAPPED_ADDRESS: u16 = 0x0020;
+const FAMILY_IPV4: u8 = 0x01;
+const FAMILY_IPV6: u8 = 0x02;
+// RFC 5389 section 7.2.1 recommends starting retransmissions at 500ms
+const INITIAL_RTO: Duration = Duration::from_millis(500);
+const MAX_MESSAGE_BYTES: usize = 1500;
+
+type TransactionId = [u8; 12];
+
+/// Fetches the IP address via STUN Binding requests to each server.
+pub async fn fetch_ip_stun(
+    servers: &[String],
+    threshold: usize,
+    version: IpVersion,
+    timeout: Duration,
+) -> Result<IpAddr> {
+    let mut set = JoinSet::new();
+    for server in servers {
+        let server = server.clone();
+        set.spawn(async move {
+            time::timeout(timeout, binding(&server, version))
+                .await
+                .with_context(|| format!("STUN request to {server} timed out"))?
+                .map_err(|error| anyhow!("STUN request failed for {server}: {error:#}"))
+        });
+    }
+
+    collect_ip_quorum(set, threshold, version, "STUN", "servers").await
+}
+
+/// Splits a `host[:port]` STUN server into its host and port, defaulting to
+/// port 3478.
+pub(crate) fn split_server(server: &str) -> Result<(&str, u16)> {
+    split_host_port(server, DEFAULT_PORT, "STUN")
+}
+
+async fn binding(server: &str, version: IpVersion) -> Result<IpAddr> {
+    let address = resolve_host_port(server, DEFAULT_PORT, "STUN", version).await?;
+    let socket = connect_udp(address).await?;
+
+    let transaction_id = transaction_id()?;
+    let request = binding_request(&transaction_id);
+    let mut buf = vec![0; MAX_MESSAGE_BYTES];
+    let mut rto = INITIAL_RTO;
+    // The caller's timeout bounds the retransmissions
+    loop {
+        socket.send(&request).await?;
+        if let Ok(response) = time::timeout(rto, receive(&socket, &mut buf, &transaction_id)).await
+        {
+            let ip = parse_binding_response(response?, &transaction_id)?;
+            return match (version, ip) {
+                (IpVersion::V4, IpAddr::V4(_)) | (IpVersion::V6, IpAddr::V6(_)) => Ok(ip),
+                _ => Err(anyhow!(
+                    "STUN server returned {ip}, expected an {version} address"
+                )),
+            };
+        }
+        rto *= 2;
+    }
+}
+
+async fn receive<'a>(
+    socket: &UdpSocket,
+    buf: &'a mut [u8],
+    transaction_id: &TransactionId,
+) -> Result<&'a [u8]> {
+    loop {
+        let len = socket.recv(buf).await?;
+        // Ignore stray datagrams that don't belong to this request
+        if len >= HEADER_LEN && buf[8..HEADER_LEN] == transaction_id[..] {
+            return Ok(&buf[..len]);
+        }
+    }
+}
+
+fn transaction_id() -> Result<TransactionId> {
+    let mut id = [0; 12];
+    SystemRandom::new()
+        .fill(&mut id)
+        .map_err(|_| anyhow!("failed to generate STUN transaction ID"))?;
+    Ok(id)
+}
+
+fn binding_request(transaction_id: &TransactionId) -> Vec<u8> {
+    let mut message = Vec::with_capacity(HEADER_LEN);
+    message.extend_from_slice(&BINDING_REQUEST.to_be_bytes());
+    message.extend_from_slice(&0u16.to_be_bytes());
+    message.extend_from_slice(&MAGIC_COOKIE.to_be_bytes());
+    message.extend_from_slice(transaction_id);
+    message
+}
+
+fn parse_binding_response(message: &[u8], transaction_id: &TransactionId) -> Result<IpAddr> {
+    if message.len() < HEADER_LEN {
+        return Err(anyhow!("STUN response truncated"));
+    }
+    let message_type = read_u16(message, 0)?;
+    let length = usize::from(read_u16(message, 2)?);
+    if read_u32(message, 4)? != MAGIC_COOKIE {
+        return Err(anyhow!("STUN response is missing the magic cookie"));
+    }
+    if message[8..HEADER_LEN] != transaction_id[..] {
+        return Err(anyhow!("STUN response transaction ID mismatch"));
+    }
+    let attributes = message
+        .get(HEADER_LEN..HEADER_LEN + length)
+        .ok_or_else(|| anyhow!("STUN response truncated"))?;
+
+    let mut mapped = None;
+    let mut xor_mapped = None;
+    let mut error = None;
+    let mut offset = 0;
+    while offset + 4 <= attributes.len() {
+        let kind = read_u16(attributes, offset)?;
+        let len = usize::from(read_u16(attributes, offset + 2)?);
+        let value = attributes
+            .get(offset + 4..offset + 4 + len)
+            .ok_or_else(|| anyhow!("STUN attribute truncated"))?;
+        match kind {
+            ATTR_XOR_MAPPED_ADDRESS => xor_mapped = Some(value),
+            ATTR_MAPPED_ADDRESS => mapped = Some(value),
+            ATTR_ERROR_CODE => error = Some(value),
+            _ => {}
+        }
+        // Attribute values are padded to a multiple of 4 bytes
+        offset += 4 + len.next_multiple_of(4);
+    }
+
+    match message_type {
+        BINDING_SUCCESS => {
+            if let Some(value) = xor_mapped {
+                parse_address(value, Some(transaction_id))
+            } else if let Some(value) = mapped {
+                // Servers predating RFC 5389 only send MAPPED-ADDRESS
+                parse_address(value, None)
+            } else {
+                Err(anyhow!("STUN response has no mapped address"))
+            }
+        }
+        BINDING_ERROR => Err(match error {
+            Some(value) if value.len() >= 4 => {
+                let code = u16::from(value[2] & 0x07) * 100 + u16::from(value[3]);
+                let reason = String::from_utf8_lossy(&value[4..]);
+                anyhow!("STUN server returned error {code}: {}", reason.trim())
+            }
+            _ => anyhow!("STUN server returned an error response"),
+        }),
+        message_type => Err(anyhow!("unexpected STUN message type {message_type:#06x}")),
+    }
+}
+
+/// Decodes a (XOR-)MAPPED-ADDRESS value, XOR-ing with the magic cookie and
+/// transaction ID when one is given.
+fn parse_address(value: &[u8], transaction_id: Option<&TransactionId>) -> Result<IpAddr> {
+    let family = *value
+        .get(1)
+        .ok_or_else(|| anyhow!("STUN address attribute truncated"))?;
+    let mut mask = [0; 16];
+    if let Some(transaction_id) = transaction_id {
+        mask[..4].copy_from_slice(&MAGIC_COOKIE.to_be_bytes());
+        mask[4..].copy_from_slice(transaction_id);
+    }
+
+    match family {
+        FAMILY_IPV4 => {
+            let bytes: [u8; 4] = value
+                .get(4..8)
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| anyhow!("STUN IPv4 address attribute truncated"))?;
+            let bytes: [u8; 4] = std::array::from_fn(|i| bytes[i] ^ mask[i]);
+            Ok(IpAddr::V4(Ipv4Addr::from(bytes)))
+        }
+        FAMILY_IPV6 => {
+            let bytes: [u8; 16] = value
+                .get(4..20)
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| anyhow!("STUN IPv6 address attribute truncated"))?;
+            let bytes: [u8; 16] = std::array::from_fn(|i| bytes[i] ^ mask[i]);
+            Ok(IpAddr::V6(Ipv6Addr::from(bytes)))
+        }
+        family => Err(anyhow!("unknown STUN address family {family:#04x}")),
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| anyhow!("STUN message truncated"))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| anyhow!("STUN message truncated"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    use std::time::Duration;
+
+    use tokio::net::UdpSocket;
+
+    use crate::ip::IpVersion;
+
+    use super::*;
+
+    /// What a test STUN server does with each request it receives
+    #[derive(Clone, Copy)]
+    enum Reply {
+        Mapped(IpAddr),
+        Error(u16, &'static str),
+        Drop,
+    }
+
+    fn response(request: &[u8], message_type: u16, attributes: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (kind, value) in attributes {
+            body.extend_from_slice(&kind.to_be_bytes());
+            body.extend_from_slice(&u16::try_from(value.len()).unwrap().to_be_bytes());
+            body.extend_from_slice(value);
+            body.resize(body.len().next_multiple_of(4), 0);
+        }
+        let mut message = Vec::new();
+        message.extend_from_slice(&message_type.to_be_bytes());
+        message.extend_from_slice(&u16::try_from(body.len()).unwrap().to_be_bytes());
+        message.extend_from_slice(&request[4..HEADER_LEN]);
+        message.extend_from_slice(&body);
+        message
+    }
+
+    fn xor_mapped_address(request: &[u8], ip: IpAddr) -> Vec<u8> {
+        let mut mask = Vec::from(&request[4..HEADER_LEN]);
+        let (family, address) = match ip {
+            IpAddr::V4(ip) => (FAMILY_IPV4, ip.octets().to_vec()),
+            IpAddr::V6(ip) => (FAMILY_IPV6, ip.octets().to_vec()),
+        };
+        mask.truncate(address.len());
+        let port = 0xd431_u16 ^ 0x2112;
+        let mut value = vec![0, family];
+        value.extend_from_slice(&port.to_be_bytes());
+        value.extend(address.iter().zip(mask).map(|(byte, mask)| byte ^ mask));
+        value
+    }
+
+    /// Local STUN responder answering requests in order with `replies`
+    async fn stun_server(replies: Vec<Reply>) -> String {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let address = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = vec![0; MAX_MESSAGE_BYTES];
+            for reply in replies {
+                let (len, peer) = socket.recv_from(&mut buf).await.unwrap();
+                let request = &buf[..len];
+                assert_eq!(read_u16(request, 0).unwrap(), BINDING_REQUEST);
+                assert_eq!(read_u32(request, 4).unwrap(), MAGIC_COOKIE);
+                let message = match reply {
+                    Reply::Mapped(ip) => response(
+                        request,
+                        BINDING_SUCCESS,
+                        &[(ATTR_XOR_MAPPED_ADDRESS, xor_mapped_address(request, ip))],
+                    ),
+                    Reply::Error(code, reason) => {
+                        let mut value = vec![0, 0, u8::try_from(code / 100).unwrap()];
+                        value.push(u8::try_from(code % 100).unwrap());
+                        value.extend_from_slice(reason.as_bytes());
+                        response(request, BINDING_ERROR, &[(ATTR_ERROR_CODE, value)])
+                    }
+                    Reply::Drop => continue,
+                };
+                socket.send_to(&message, peer).await.unwrap();
+            }
+        });
+        address.to_string()
+    }
+
+    const TIMEOUT: Duration = Duration::from_secs(3);
+
+    #[tokio::test]
+    async fn fetch_ip_stun_reaches_quorum() {
+        let ip = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10));
+        let other = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 11));
+        let servers = vec![
+            stun_server(vec![Reply::Mapped(ip)]).await,
+            stun_server(vec![Reply::Mapped(other)]).await,
+            stun_server(vec![Reply::Mapped(ip)]).await,
+        ];
+
+        let result = fetch_ip_stun(&servers, 2, IpVersion::V4, TIMEOUT)
+            .await
+            .unwrap();
+
+        assert_eq!(result, ip);
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_stun_retransmits_lost_requests() {
+        let ip = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10));
+        let servers = vec![stun_server(vec![Reply::Drop, Reply::Mapped(ip)]).await];
+
+        let result = fetch_ip_stun(&servers, 1, IpVersion::V4, TIMEOUT)
+            .await
+            .unwrap();
+
+        assert_eq!(result, ip);
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_stun_reports_error_responses() {
+        let ip = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 10));
+        let servers = vec![
+            stun_server(vec![Reply::Error(420, "Unknown Attribute")]).await,
+            stun_server(vec![Reply::Mapped(ip)]).await,
+        ];
+
+        let error = fetch_ip_stun(&servers, 2, IpVersion::V4, TIMEOUT)
+            .await
+            .unwrap_err();
+        let message = error.to_string();
+
+        assert!(
+            message.contains(
+                "no STUN IP lookup quorum reached for IPv4: required 2 matching responses from 2 servers"
+            ),
+            "{message}"
+        );
+        assert!(message.contains("192.0.2.10=1"), "{message}");
+        assert!(
+            message.contains("STUN server returned error 420: Unknown Attribute"),
+            "{message}"
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_ip_stun_rejects_address_of_other_version() {
+        let servers = vec![stun_server(vec![Reply::Mapped(IpAddr::V6(Ipv6Addr::LOCALHOST))]).await];
+
+        let error = fetch_ip_stun(&servers, 1, IpVersion::V4, TIMEOUT)
+            .await
+            .unwrap_err();
+
+        assert!(
+            error
+                .to_string()
+                .contains("STUN server returned ::1, expected an IPv4 address"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn parses_xor_mapped_ipv6_address() {
+        let transaction_id = [7; 12];
+        let request = binding_request(&transaction_id);
+        let ip = IpAddr::V6("2001:db8::1".parse().unwrap());
+        let message = response(
+            &request,
+            BINDING_SUCCESS,
+            &[(ATTR_XOR_MAPPED_ADDRESS, xor_mapped_address(&request, ip))],
+        );
+
+        assert_eq!(
+            parse_binding_response(&message, &transaction_id).unwrap(),
+            ip
+        );
+    }
+
+    #[test]
+    fn falls_back_to_mapped_address() {
+        let transaction_id = [7; 12];
+        let request = binding_request(&transaction_id);
+        let message = response(
+            &request,
+            BINDING_SUCCESS,
+            &[(
+                ATTR_MAPPED_ADDRESS,
+                vec![0, FAMILY_IPV4, 0x0d, 0x96, 192, 0, 2, 1],
+            )],
+        );
+
+        assert_eq!(
+            parse_binding_response(&message, &transaction_id).unwrap(),
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))
+        );
+    }
+
+    #[test]
+    fn splits_stun_servers() {
+        assert_eq!(
+            split_server("stun.example.com").unwrap(),
+            ("stun.example.com", 3478)
+        );
+        assert_eq!(
+            split_server("stun.example.com:19302").unwrap(),
+            ("stun.example.com", 19302)
+        );
+        assert_eq!(
+            split_server("[2001:db8::1]:3479").unwrap(),
+            ("2001:db8::1", 3479)
+        );
+        assert_eq!(split_server("2001:db8::1").unwrap(), ("2001:db8::1", 3478));
+        assert!(
+            split_server("stun.example.com:http")
+                .unwrap_err()
+                .to_string()
+                .contains("invalid port")
+        );
+    }
+}
@@ -5,11 +5,34 @@ use async_trait::async_trait;
 use dyn_clone::DynClone;
 use reqwest_middleware::ClientWithMiddleware as HttpClient;
 
+use crate::config::IpVersions;
 use crate::ip::IpUpdate;
 
+mod adguard_home;
+mod azure_dns;
+mod bunny;
 mod cloudflare;
+mod desec;
+mod digitalocean;
+mod dnsimple;
 mod duckdns;
+mod dyndns2;
+mod freedns;
+mod gandi;
+mod godaddy;
+mod google_cloud_dns;
+mod he;
+mod hetzner;
+mod linode;
+mod namecheap;
+mod oauth;
+mod ovh;
+mod pihole;
 mod porkbun;
+mod powerdns;
+mod rfc2136;
+mod route53;
+mod technitium;
 
 /// DNS update provider.
 #[async_trait]
@@ -17,6 +40,15 @@ mod porkbun;
 pub trait Provider: Debug + DynClone + Send + Sync {
     fn validate_config(&self) -> Result<()>;
 
+    /// Reject configured IP versions the provider cannot update.
+    ///
+    /// `versions` is a top-level config field and providers are deserialized
+    /// on their own, so `validate_config` cannot see it. `Config` calls this
+    /// while it is built, so an invalid combination is rejected at load time.
+    fn validate_versions(&self, _versions: IpVersions) -> Result<()> {
+        Ok(())
+    }
+
     async fn update(&self, update: IpUpdate, request: HttpClient) -> Result<bool>;
 }
 
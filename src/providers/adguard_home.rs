@@ -0,0 +1,345 @@
+use std::net::IpAddr;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use compact_str::CompactString;
+use reqwest::Response;
+use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use serde_json::json;
+use smallvec::SmallVec;
+
+use crate::ip::IpUpdate;
+use crate::providers::Provider;
+
+/// `AdGuard` Home DNS rewrite update provider
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct AdGuardHome {
+    api_url: String,
+    username: CompactString,
+    password: SecretString,
+    domains: SmallVec<[Domain; 2]>,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct Domain {
+    name: CompactString,
+}
+
+#[derive(Debug, Deserialize)]
+struct Rewrite {
+    domain: String,
+    answer: String,
+}
+
+impl AdGuardHome {
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/control/{path}", self.api_url.trim_end_matches('/'))
+    }
+
+    async fn post_rewrite(
+        &self,
+        request: &HttpClient,
+        path: &str,
+        domain: &str,
+        answer: &str,
+        action: &str,
+    ) -> Result<()> {
+        let response = request
+            .post(self.endpoint(path))
+            .basic_auth(&self.username, Some(self.password.expose_secret()))
+            .json(&json!({ "domain": domain, "answer": answer }))
+            .send()
+            .await?;
+        check_adguard_response(response, action).await?;
+        Ok(())
+    }
+}
+
+async fn check_adguard_response(response: Response, action: &str) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    // AdGuard Home reports errors as plain text
+    let body = response.text().await?;
+    let detail = body_snippet(&body).map_or_else(String::new, |body| format!(": {body}"));
+    Err(anyhow!("failed to {action}: HTTP {status}{detail}"))
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[async_trait]
+#[typetag::deserialize(name = "adguard_home")]
+impl Provider for AdGuardHome {
+    fn validate_config(&self) -> Result<()> {
+        if self.api_url.trim().is_empty() {
+            return Err(anyhow!("AdGuard Home api_url must not be empty"));
+        }
+        if self.username.trim().is_empty() {
+            return Err(anyhow!("AdGuard Home username must not be empty"));
+        }
+        if self.domains.is_empty() {
+            return Err(anyhow!("no domains configured for AdGuard Home provider"));
+        }
+        if self
+            .domains
+            .iter()
+            .any(|domain| domain.name.trim().is_empty())
+        {
+            return Err(anyhow!("AdGuard Home domain names must not be empty"));
+        }
+        Ok(())
+    }
+
+    async fn update(&self, update: IpUpdate, request: HttpClient) -> Result<bool> {
+        let response = request
+            .get(self.endpoint("rewrite/list"))
+            .basic_auth(&self.username, Some(self.password.expose_secret()))
+            .send()
+            .await?;
+        let rewrites = check_adguard_response(response, "list AdGuard Home DNS rewrites")
+            .await?
+            .json::<Vec<Rewrite>>()
+            .await?;
+
+        let mut changed = false;
+        for domain in &self.domains {
+            for (version, addr) in update.iter() {
+                // Rewrites answering with a hostname or a record type are not ours to touch
+                let stale = rewrites
+                    .iter()
+                    .filter(|rewrite| rewrite.domain.eq_ignore_ascii_case(&domain.name))
+                    .filter_map(|rewrite| {
+                        let address = rewrite.answer.parse::<IpAddr>().ok()?;
+                        (address.is_ipv4() == addr.is_ipv4()).then_some((address, rewrite))
+                    })
+                    .collect::<Vec<_>>();
+                if let [(address, _)] = stale.as_slice()
+                    && *address == addr
+                {
+                    continue;
+                }
+
+                let action = format!(
+                    "update AdGuard Home DNS rewrite ({}) for {version}",
+                    domain.name
+                );
+                // Add before removing so the name always resolves
+                if !stale.iter().any(|(address, _)| *address == addr) {
+                    self.post_rewrite(
+                        &request,
+                        "rewrite/add",
+                        &domain.name,
+                        &addr.to_string(),
+                        &action,
+                    )
+                    .await?;
+                }
+                for (address, rewrite) in stale {
+                    if address == addr {
+                        continue;
+                    }
+                    self.post_rewrite(
+                        &request,
+                        "rewrite/delete",
+                        &rewrite.domain,
+                        &rewrite.answer,
+                        &action,
+                    )
+                    .await?;
+                }
+                changed = true;
+            }
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use smallvec::smallvec;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_json, header, method, path},
+    };
+
+    use super::*;
+
+    const UPDATE_BOTH: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+    const UPDATE_V4: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: None,
+    };
+
+    // admin:secret
+    const BASIC_AUTH: &str = "Basic YWRtaW46c2VjcmV0";
+
+    fn provider(api_url: String) -> AdGuardHome {
+        AdGuardHome {
+            api_url,
+            username: "admin".into(),
+            password: "secret".into(),
+            domains: smallvec![Domain {
+                name: "wan.lan".into(),
+            }],
+        }
+    }
+
+    async fn mount_rewrites(mock: &MockServer, rewrites: serde_json::Value) {
+        Mock::given(method("GET"))
+            .and(path("/control/rewrite/list"))
+            .and(header("authorization", BASIC_AUTH))
+            .respond_with(ResponseTemplate::new(200).set_body_json(rewrites))
+            .expect(1)
+            .mount(mock)
+            .await;
+    }
+
+    #[test]
+    fn test_adguard_home_deserializes_from_config() {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+[[providers]]
+type = "adguard_home"
+api_url = "http://192.168.1.2:3000"
+username = "admin"
+password = "secret"
+
+[[providers.domains]]
+name = "wan.lan"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.providers.len(), 1);
+        config.providers[0].validate_config().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_adguard_home_replaces_stale_rewrites() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        mount_rewrites(
+            &mock,
+            json!([
+                {"domain": "wan.lan", "answer": "192.0.2.1"},
+                {"domain": "wan.lan", "answer": "router.lan"},
+                {"domain": "nas.lan", "answer": "192.0.2.1"}
+            ]),
+        )
+        .await;
+        for answer in ["127.0.0.1", "::1"] {
+            Mock::given(method("POST"))
+                .and(path("/control/rewrite/add"))
+                .and(header("authorization", BASIC_AUTH))
+                .and(body_json(json!({"domain": "wan.lan", "answer": answer})))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&mock)
+                .await;
+        }
+        Mock::given(method("POST"))
+            .and(path("/control/rewrite/delete"))
+            .and(body_json(
+                json!({"domain": "wan.lan", "answer": "192.0.2.1"}),
+            ))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_BOTH, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_adguard_home_skips_current_rewrites() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        mount_rewrites(&mock, json!([{"domain": "wan.lan", "answer": "127.0.0.1"}])).await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_V4, http).await.unwrap();
+        assert!(!result);
+    }
+
+    #[tokio::test]
+    async fn test_adguard_home_surfaces_errors() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        mount_rewrites(&mock, json!([])).await;
+        Mock::given(method("POST"))
+            .and(path("/control/rewrite/add"))
+            .respond_with(
+                ResponseTemplate::new(400).set_body_string("rewrite: duplicate rewrite\n"),
+            )
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to update AdGuard Home DNS rewrite (wan.lan) for IPv4: HTTP 400 Bad Request: rewrite: duplicate rewrite"
+        );
+    }
+
+    #[test]
+    fn test_adguard_home_validates_config() {
+        let mut provider = provider("http://192.168.1.2:3000".to_string());
+        provider.validate_config().unwrap();
+
+        provider.domains[0].name = " ".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "AdGuard Home domain names must not be empty"
+        );
+
+        provider.username = "".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "AdGuard Home username must not be empty"
+        );
+    }
+}
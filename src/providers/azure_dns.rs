@@ -0,0 +1,496 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use compact_str::CompactString;
+use reqwest::{Response, StatusCode};
+use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use serde_json::json;
+use smallvec::SmallVec;
+
+use crate::ip::{IpUpdate, IpVersion};
+use crate::providers::Provider;
+use crate::providers::oauth::TokenCache;
+
+const API_VERSION: &str = "2018-05-01";
+
+/// Azure DNS update provider
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct AzureDns {
+    tenant_id: SecretString,
+    client_id: SecretString,
+    client_secret: SecretString,
+    subscription_id: CompactString,
+    resource_group: CompactString,
+    zone: CompactString,
+    domains: SmallVec<[Domain; 2]>,
+    #[serde(default = "default_authority_url")]
+    authority_url: String,
+    #[serde(default = "default_management_url")]
+    management_url: String,
+    #[serde(skip)]
+    token: TokenCache,
+}
+
+fn default_authority_url() -> String {
+    "https://login.microsoftonline.com".to_string()
+}
+
+fn default_management_url() -> String {
+    "https://management.azure.com".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct Domain {
+    name: CompactString,
+    #[serde(default = "default_ttl")]
+    ttl: u32,
+}
+
+fn default_ttl() -> u32 {
+    300
+}
+
+#[derive(Debug, Deserialize)]
+struct AzureErrorResponse {
+    error: AzureError,
+}
+
+#[derive(Debug, Deserialize)]
+struct AzureError {
+    code: Option<String>,
+    message: Option<String>,
+}
+
+impl AzureDns {
+    fn token_url(&self) -> String {
+        format!(
+            "{}/{}/oauth2/v2.0/token",
+            self.authority_url.trim_end_matches('/'),
+            self.tenant_id.expose_secret()
+        )
+    }
+
+    fn scope(&self) -> String {
+        format!("{}/.default", self.management_url.trim_end_matches('/'))
+    }
+
+    fn record_set_url(&self, record_type: &str, name: &str) -> String {
+        format!(
+            "{}/subscriptions/{}/resourceGroups/{}/providers/Microsoft.Network/dnsZones/{}/{record_type}/{name}",
+            self.management_url.trim_end_matches('/'),
+            self.subscription_id,
+            self.resource_group,
+            self.zone
+        )
+    }
+
+    fn fqdn(&self, name: &str) -> String {
+        if name == "@" {
+            self.zone.to_string()
+        } else {
+            format!("{name}.{}", self.zone)
+        }
+    }
+
+    async fn access_token(&self, request: &HttpClient) -> Result<SecretString> {
+        if let Some(token) = self.token.get() {
+            return Ok(token);
+        }
+
+        let body = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("grant_type", "client_credentials")
+            .append_pair("client_id", self.client_id.expose_secret())
+            .append_pair("client_secret", self.client_secret.expose_secret())
+            .append_pair("scope", &self.scope())
+            .finish();
+        let response = request
+            .post(self.token_url())
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/x-www-form-urlencoded",
+            )
+            .body(body)
+            .send()
+            .await?;
+
+        self.token.store(response, "Azure").await
+    }
+
+    async fn put_record_set(
+        &self,
+        request: &HttpClient,
+        token: &SecretString,
+        domain: &Domain,
+        version: IpVersion,
+        value: String,
+    ) -> Result<()> {
+        let (record_type, properties) = match version {
+            IpVersion::V4 => (
+                "A",
+                json!({ "TTL": domain.ttl, "ARecords": [{ "ipv4Address": value }] }),
+            ),
+            IpVersion::V6 => (
+                "AAAA",
+                json!({ "TTL": domain.ttl, "AAAARecords": [{ "ipv6Address": value }] }),
+            ),
+        };
+        let response = request
+            .put(self.record_set_url(record_type, &domain.name))
+            .query(&[("api-version", API_VERSION)])
+            .json(&json!({ "properties": properties }))
+            .bearer_auth(token.expose_secret())
+            .send()
+            .await?;
+
+        // A rejected token is dropped so the next update fetches a fresh one
+        if response.status() == StatusCode::UNAUTHORIZED {
+            self.token.clear();
+        }
+        parse_azure_response(
+            response,
+            &format!(
+                "update Azure DNS ({}) {record_type} record",
+                self.fqdn(&domain.name)
+            ),
+        )
+        .await
+    }
+}
+
+async fn parse_azure_response(response: Response, action: &str) -> Result<()> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+
+    let body = response.text().await?;
+    let detail = serde_json::from_str::<AzureErrorResponse>(&body)
+        .ok()
+        .and_then(
+            |response| match (response.error.code, response.error.message) {
+                (Some(code), Some(message)) => Some(format!("{code}: {message}")),
+                (None, detail) | (detail, None) => detail,
+            },
+        )
+        .or_else(|| body_snippet(&body))
+        .map_or_else(String::new, |detail| format!(": {detail}"));
+
+    Err(anyhow!("failed to {action}: HTTP {status}{detail}"))
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[async_trait]
+#[typetag::deserialize(name = "azure_dns")]
+impl Provider for AzureDns {
+    fn validate_config(&self) -> Result<()> {
+        for (field, value) in [
+            ("tenant_id", &self.tenant_id),
+            ("client_id", &self.client_id),
+            ("client_secret", &self.client_secret),
+        ] {
+            if value.expose_secret().trim().is_empty() {
+                return Err(anyhow!("Azure DNS {field} must not be empty"));
+            }
+        }
+        for (field, value) in [
+            ("subscription_id", &self.subscription_id),
+            ("resource_group", &self.resource_group),
+            ("zone", &self.zone),
+        ] {
+            if value.trim().is_empty() {
+                return Err(anyhow!("Azure DNS {field} must not be empty"));
+            }
+        }
+        if self.domains.is_empty() {
+            return Err(anyhow!("no domains configured for Azure DNS provider"));
+        }
+        for domain in &self.domains {
+            if domain.name.trim().is_empty() {
+                return Err(anyhow!(
+                    "Azure DNS domain names must not be empty, use @ for the zone apex"
+                ));
+            }
+            if domain.name == self.zone || domain.name.ends_with(&format!(".{}", self.zone)) {
+                return Err(anyhow!(
+                    "Azure DNS domain ({}) must be relative to the zone ({})",
+                    domain.name,
+                    self.zone
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn update(&self, update: IpUpdate, request: HttpClient) -> Result<bool> {
+        let token = self.access_token(&request).await?;
+        for domain in &self.domains {
+            for (version, addr) in update.iter() {
+                self.put_record_set(&request, &token, domain, version, addr.to_string())
+                    .await?;
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use smallvec::smallvec;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{bearer_token, body_json, body_string_contains, method, path, query_param},
+    };
+
+    use super::*;
+
+    const UPDATE_BOTH: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+    const UPDATE_V4: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: None,
+    };
+
+    const ZONE_PATH: &str = "/subscriptions/sub-id/resourceGroups/dns-rg/providers/Microsoft.Network/dnsZones/example.com";
+
+    fn provider(url: &str) -> AzureDns {
+        AzureDns {
+            tenant_id: "tenant-id".into(),
+            client_id: "client-id".into(),
+            client_secret: "client-secret".into(),
+            subscription_id: "sub-id".into(),
+            resource_group: "dns-rg".into(),
+            zone: "example.com".into(),
+            domains: smallvec![
+                Domain {
+                    name: "@".into(),
+                    ttl: 300,
+                },
+                Domain {
+                    name: "www".into(),
+                    ttl: 60,
+                },
+            ],
+            authority_url: url.to_string(),
+            management_url: url.to_string(),
+            token: TokenCache::default(),
+        }
+    }
+
+    async fn mount_token(mock: &MockServer, expires_in: u64, expected: u64) {
+        Mock::given(method("POST"))
+            .and(path("/tenant-id/oauth2/v2.0/token"))
+            .and(body_string_contains("grant_type=client_credentials"))
+            .and(body_string_contains("client_id=client-id"))
+            .and(body_string_contains("client_secret=client-secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "token_type": "Bearer",
+                "expires_in": expires_in,
+                "access_token": "access-token"
+            })))
+            .expect(expected)
+            .mount(mock)
+            .await;
+    }
+
+    #[test]
+    fn test_azure_dns_deserializes_from_config() {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+[[providers]]
+type = "azure_dns"
+tenant_id = "tenant-id"
+client_id = "client-id"
+client_secret = "client-secret"
+subscription_id = "sub-id"
+resource_group = "dns-rg"
+zone = "example.com"
+
+[[providers.domains]]
+name = "@"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.providers.len(), 1);
+        config.providers[0].validate_config().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_azure_dns_puts_record_sets() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(&mock.uri());
+
+        mount_token(&mock, 3599, 1).await;
+        for (name, record_type, properties) in [
+            (
+                "@",
+                "A",
+                json!({"TTL": 300, "ARecords": [{"ipv4Address": "127.0.0.1"}]}),
+            ),
+            (
+                "@",
+                "AAAA",
+                json!({"TTL": 300, "AAAARecords": [{"ipv6Address": "::1"}]}),
+            ),
+            (
+                "www",
+                "A",
+                json!({"TTL": 60, "ARecords": [{"ipv4Address": "127.0.0.1"}]}),
+            ),
+            (
+                "www",
+                "AAAA",
+                json!({"TTL": 60, "AAAARecords": [{"ipv6Address": "::1"}]}),
+            ),
+        ] {
+            Mock::given(method("PUT"))
+                .and(path(format!("{ZONE_PATH}/{record_type}/{name}")))
+                .and(query_param("api-version", API_VERSION))
+                .and(bearer_token("access-token"))
+                .and(body_json(json!({ "properties": properties })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+                .expect(1)
+                .mount(&mock)
+                .await;
+        }
+
+        let result = provider.update(UPDATE_BOTH, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_azure_dns_caches_token_until_expiry() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(&mock.uri());
+
+        mount_token(&mock, 3599, 1).await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .mount(&mock)
+            .await;
+
+        // Clones share the cached token, as providers are cloned per update
+        provider
+            .clone()
+            .update(UPDATE_V4, http.clone())
+            .await
+            .unwrap();
+        provider.update(UPDATE_V4, http).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_azure_dns_refreshes_expiring_token() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(&mock.uri());
+
+        // Tokens inside the expiry margin are never reused
+        mount_token(&mock, 30, 2).await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .mount(&mock)
+            .await;
+
+        provider.update(UPDATE_V4, http.clone()).await.unwrap();
+        provider.update(UPDATE_V4, http).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_azure_dns_clears_rejected_token() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(&mock.uri());
+
+        mount_token(&mock, 3599, 1).await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+                "error": {
+                    "code": "ExpiredAuthenticationToken",
+                    "message": "The access token expiry UTC time is earlier than current UTC time."
+                }
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to update Azure DNS (example.com) A record: HTTP 401 Unauthorized: ExpiredAuthenticationToken: The access token expiry UTC time is earlier than current UTC time."
+        );
+        assert!(provider.token.get().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_azure_dns_token_error() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(&mock.uri());
+
+        Mock::given(method("POST"))
+            .and(path("/tenant-id/oauth2/v2.0/token"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+                "error": "invalid_client",
+                "error_description": "AADSTS7000215: Invalid client secret provided."
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to fetch Azure access token: HTTP 401 Unauthorized: invalid_client: AADSTS7000215: Invalid client secret provided."
+        );
+    }
+
+    #[test]
+    fn test_azure_dns_validates_config() {
+        let mut provider = provider(&default_management_url());
+        provider.validate_config().unwrap();
+        assert_eq!(provider.scope(), "https://management.azure.com/.default");
+
+        provider.domains[1].name = "www.example.com".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "Azure DNS domain (www.example.com) must be relative to the zone (example.com)"
+        );
+
+        provider.client_secret = "".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "Azure DNS client_secret must not be empty"
+        );
+    }
+}
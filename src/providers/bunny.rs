@@ -0,0 +1,507 @@
+use std::net::IpAddr;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use compact_str::CompactString;
+use reqwest::Response;
+use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use smallvec::SmallVec;
+
+use crate::ip::{IpUpdate, IpVersion};
+use crate::providers::Provider;
+
+const AUTH_HEADER: &str = "AccessKey";
+const ZONES_PER_PAGE: &str = "1000";
+
+// Bunny identifies record types by number
+const RECORD_TYPE_A: u8 = 0;
+const RECORD_TYPE_AAAA: u8 = 1;
+
+/// Bunny DNS update provider
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Bunny {
+    zone: CompactString,
+    api_key: SecretString,
+    domains: SmallVec<[Domain; 2]>,
+    #[serde(default = "default_api_url")]
+    api_url: String,
+}
+
+fn default_api_url() -> String {
+    "https://api.bunny.net".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+struct Domain {
+    name: CompactString,
+    #[serde(default = "default_ttl")]
+    ttl: u32,
+    disabled: bool,
+}
+
+fn default_ttl() -> u32 {
+    300
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct RecordBody<'a> {
+    #[serde(rename = "Type")]
+    record_type: u8,
+    name: &'a str,
+    value: &'a IpAddr,
+    ttl: u32,
+    disabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BunnyError {
+    field: Option<String>,
+    message: Option<String>,
+}
+
+impl BunnyError {
+    fn summary(self) -> Option<String> {
+        match (self.field, self.message) {
+            (Some(field), Some(message)) if !field.is_empty() => {
+                Some(format!("{field}: {message}"))
+            }
+            (_, message) => message.filter(|message| !message.is_empty()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ZonesResult {
+    items: Vec<ZoneResult>,
+    #[serde(default)]
+    has_more_items: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ZoneResult {
+    id: u64,
+    domain: String,
+    #[serde(default)]
+    records: Vec<RecordResult>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RecordResult {
+    id: u64,
+    #[serde(rename = "Type")]
+    kind: u8,
+    name: String,
+    value: String,
+    ttl: u32,
+    #[serde(default)]
+    disabled: bool,
+}
+
+impl Bunny {
+    async fn fetch_zone(&self, request: &HttpClient) -> Result<ZoneResult> {
+        let mut page = 1u32;
+        loop {
+            let response = request
+                .get(format!("{}/dnszone", self.api_url.trim_end_matches('/')))
+                .query(&[
+                    ("page", page.to_string().as_str()),
+                    ("perPage", ZONES_PER_PAGE),
+                    ("search", self.zone.as_str()),
+                ])
+                .header(AUTH_HEADER, self.api_key.expose_secret())
+                .send()
+                .await?;
+            let result =
+                parse_bunny_response::<ZonesResult>(response, "list Bunny DNS zones").await?;
+            if let Some(zone) = result
+                .items
+                .into_iter()
+                .find(|zone| zone.domain.eq_ignore_ascii_case(&self.zone))
+            {
+                return Ok(zone);
+            }
+            if !result.has_more_items {
+                return Err(anyhow!(
+                    "failed to find a matching Bunny DNS zone ({})",
+                    self.zone
+                ));
+            }
+            page += 1;
+        }
+    }
+
+    async fn update_dns_record(
+        &self,
+        request: &HttpClient,
+        zone_id: u64,
+        record_id: u64,
+        body: &RecordBody<'_>,
+        domain: &Domain,
+    ) -> Result<()> {
+        let response = request
+            .post(format!(
+                "{}/dnszone/{zone_id}/records/{record_id}",
+                self.api_url.trim_end_matches('/')
+            ))
+            .json(body)
+            .header(AUTH_HEADER, self.api_key.expose_secret())
+            .send()
+            .await?;
+        parse_bunny_response::<Option<Value>>(
+            response,
+            &format!("update Bunny DNS domain ({}) record", domain.name),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn create_dns_record(
+        &self,
+        request: &HttpClient,
+        zone_id: u64,
+        body: &RecordBody<'_>,
+        domain: &Domain,
+    ) -> Result<()> {
+        let response = request
+            .put(format!(
+                "{}/dnszone/{zone_id}/records",
+                self.api_url.trim_end_matches('/')
+            ))
+            .json(body)
+            .header(AUTH_HEADER, self.api_key.expose_secret())
+            .send()
+            .await?;
+        parse_bunny_response::<Option<Value>>(
+            response,
+            &format!("create Bunny DNS domain ({}) record", domain.name),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Record name relative to the zone, empty for the apex.
+    fn record_name<'a>(&self, domain: &'a Domain) -> &'a str {
+        domain
+            .name
+            .strip_suffix(self.zone.as_str())
+            .and_then(|name| name.strip_suffix('.'))
+            .unwrap_or_default()
+    }
+}
+
+async fn parse_bunny_response<T>(response: Response, action: &str) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let status = response.status();
+    let body = response.text().await?;
+
+    if !status.is_success() {
+        let detail = serde_json::from_str::<BunnyError>(&body)
+            .ok()
+            .and_then(BunnyError::summary)
+            .or_else(|| body_snippet(&body));
+        let detail = detail.map_or_else(String::new, |detail| format!(": {detail}"));
+
+        return Err(anyhow!("failed to {action}: HTTP {status}{detail}"));
+    }
+
+    // Updates answer 204 without a body
+    let body = if body.trim().is_empty() {
+        "null"
+    } else {
+        &body
+    };
+    serde_json::from_str::<T>(body)
+        .map_err(|error| anyhow!("failed to parse Bunny DNS response for {action}: {error}"))
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[async_trait]
+#[typetag::deserialize(name = "bunny")]
+impl Provider for Bunny {
+    fn validate_config(&self) -> Result<()> {
+        if self.zone.trim().is_empty() {
+            return Err(anyhow!("Bunny DNS zone must not be empty"));
+        }
+        if self.api_key.expose_secret().trim().is_empty() {
+            return Err(anyhow!("Bunny DNS api_key must not be empty"));
+        }
+        if self.domains.is_empty() {
+            return Err(anyhow!("no domains configured for Bunny DNS provider"));
+        }
+        for domain in &self.domains {
+            if domain.name != self.zone && !domain.name.ends_with(&format!(".{}", self.zone)) {
+                return Err(anyhow!(
+                    "Bunny DNS domain ({}) is not within zone ({})",
+                    domain.name,
+                    self.zone
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn update(&self, update: IpUpdate, request: HttpClient) -> Result<bool> {
+        let zone = self.fetch_zone(&request).await?;
+        let mut changed = false;
+        for domain in &self.domains {
+            let name = self.record_name(domain);
+            for (version, addr) in update.iter() {
+                let record_type = match version {
+                    IpVersion::V4 => RECORD_TYPE_A,
+                    IpVersion::V6 => RECORD_TYPE_AAAA,
+                };
+                let body = RecordBody {
+                    record_type,
+                    name,
+                    value: &addr,
+                    ttl: domain.ttl,
+                    disabled: domain.disabled,
+                };
+                if let Some(record) = zone.records.iter().find(|record| {
+                    record.kind == record_type && record.name.eq_ignore_ascii_case(name)
+                }) {
+                    if record.value.parse::<IpAddr>().ok() == Some(addr)
+                        && record.ttl == domain.ttl
+                        && record.disabled == domain.disabled
+                    {
+                        continue;
+                    }
+                    self.update_dns_record(&request, zone.id, record.id, &body, domain)
+                        .await?;
+                } else {
+                    self.create_dns_record(&request, zone.id, &body, domain)
+                        .await?;
+                }
+                changed = true;
+            }
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use serde_json::json;
+    use smallvec::smallvec;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_json, header, method, path, query_param},
+    };
+
+    use super::*;
+
+    const UPDATE_BOTH: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+    const UPDATE_V4: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: None,
+    };
+
+    fn provider(api_url: String) -> Bunny {
+        Bunny {
+            zone: "example.com".into(),
+            api_key: "secret".into(),
+            domains: smallvec![
+                Domain {
+                    name: "example.com".into(),
+                    ttl: 300,
+                    disabled: false,
+                },
+                Domain {
+                    name: "staging.example.com".into(),
+                    ttl: 60,
+                    disabled: true,
+                },
+            ],
+            api_url,
+        }
+    }
+
+    async fn mount_zone(mock: &MockServer, records: &Value) {
+        Mock::given(method("GET"))
+            .and(path("/dnszone"))
+            .and(query_param("search", "example.com"))
+            .and(header(AUTH_HEADER, "secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "Items": [
+                    {"Id": 7, "Domain": "sub.example.com", "Records": []},
+                    {"Id": 42, "Domain": "example.com", "Records": records}
+                ],
+                "CurrentPage": 1,
+                "TotalItems": 2,
+                "HasMoreItems": false
+            })))
+            .expect(1)
+            .mount(mock)
+            .await;
+    }
+
+    #[test]
+    fn test_bunny_deserializes_from_config() {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+[[providers]]
+type = "bunny"
+zone = "example.com"
+api_key = "secret"
+
+[[providers.domains]]
+name = "example.com"
+
+[[providers.domains]]
+name = "staging.example.com"
+ttl = 60
+disabled = true
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.providers.len(), 1);
+        config.providers[0].validate_config().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_bunny_updates_and_creates_records() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        mount_zone(
+            &mock,
+            &json!([
+                {"Id": 1, "Type": 0, "Name": "", "Value": "192.0.2.1", "Ttl": 300, "Disabled": false},
+                {"Id": 2, "Type": 1, "Name": "", "Value": "::1", "Ttl": 300, "Disabled": false},
+                {"Id": 3, "Type": 3, "Name": "staging", "Value": "txt", "Ttl": 300, "Disabled": false}
+            ]),
+        )
+        .await;
+        Mock::given(method("POST"))
+            .and(path("/dnszone/42/records/1"))
+            .and(header(AUTH_HEADER, "secret"))
+            .and(body_json(json!({
+                "Type": 0, "Name": "", "Value": "127.0.0.1", "Ttl": 300, "Disabled": false
+            })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        for (record_type, value) in [(0, "127.0.0.1"), (1, "::1")] {
+            Mock::given(method("PUT"))
+                .and(path("/dnszone/42/records"))
+                .and(body_json(json!({
+                    "Type": record_type, "Name": "staging", "Value": value, "Ttl": 60, "Disabled": true
+                })))
+                .respond_with(ResponseTemplate::new(201).set_body_json(json!({"Id": 9})))
+                .expect(1)
+                .mount(&mock)
+                .await;
+        }
+
+        let result = provider.update(UPDATE_BOTH, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_bunny_skips_unchanged_records() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        mount_zone(
+            &mock,
+            &json!([
+                {"Id": 1, "Type": 0, "Name": "", "Value": "127.0.0.1", "Ttl": 300, "Disabled": false},
+                {"Id": 2, "Type": 0, "Name": "staging", "Value": "127.0.0.1", "Ttl": 60, "Disabled": true}
+            ]),
+        )
+        .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(0)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_V4, http).await.unwrap();
+        assert!(!result);
+    }
+
+    #[tokio::test]
+    async fn test_bunny_surfaces_errors() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+                "ErrorKey": "unauthorized",
+                "Field": "",
+                "Message": "Authorization has been denied for this request."
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to list Bunny DNS zones: HTTP 401 Unauthorized: Authorization has been denied for this request."
+        );
+    }
+
+    #[test]
+    fn test_bunny_validates_config() {
+        let mut provider = provider(default_api_url());
+        provider.validate_config().unwrap();
+
+        provider.domains[1].name = "staging.example.org".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "Bunny DNS domain (staging.example.org) is not within zone (example.com)"
+        );
+
+        provider.api_key = "".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "Bunny DNS api_key must not be empty"
+        );
+    }
+}
@@ -0,0 +1,718 @@
+use std::net::IpAddr;
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use compact_str::CompactString;
+use reqwest::header::{LINK, RETRY_AFTER};
+use reqwest::{Response, StatusCode};
+use reqwest_middleware::{ClientWithMiddleware as HttpClient, RequestBuilder};
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use smallvec::{SmallVec, smallvec};
+use tokio::time;
+use tracing::{debug, info, warn};
+
+use crate::ip::{IpUpdate, IpVersion};
+use crate::providers::Provider;
+
+const MAX_THROTTLE_RETRIES: u32 = 3;
+// Longer throttles are surfaced as errors rather than stalling the update loop
+const MAX_THROTTLE_DELAY: Duration = Duration::from_mins(1);
+const DEFAULT_THROTTLE_DELAY: Duration = Duration::from_secs(1);
+
+/// deSEC DNS update provider
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Desec {
+    token: SecretString,
+    domains: SmallVec<[Domain; 2]>,
+    #[serde(default = "default_api_url")]
+    api_url: String,
+}
+
+fn default_api_url() -> String {
+    "https://desec.io/api/v1".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Domain {
+    name: CompactString,
+    #[serde(default = "default_subnames")]
+    subnames: SmallVec<[CompactString; 2]>,
+    #[serde(default = "default_ttl")]
+    ttl: u32,
+}
+
+// An empty subname is the domain apex
+fn default_subnames() -> SmallVec<[CompactString; 2]> {
+    smallvec![CompactString::default()]
+}
+
+// deSEC's default minimum TTL
+fn default_ttl() -> u32 {
+    3600
+}
+
+#[derive(Debug, Deserialize)]
+struct DesecError {
+    detail: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DomainResult {
+    minimum_ttl: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+struct RrSet {
+    subname: CompactString,
+    #[serde(rename = "type")]
+    record_type: CompactString,
+    ttl: u32,
+    records: Vec<String>,
+}
+
+impl Desec {
+    async fn fetch_minimum_ttl(&self, request: &HttpClient, domain: &Domain) -> Result<u32> {
+        let url = format!("{}/domains/{}/", self.api_url, domain.name);
+        let response = self.send(self.authorize(request.get(url))).await?;
+        let result = parse_desec_response::<DomainResult>(
+            response,
+            &format!("fetch deSEC domain ({})", domain.name),
+        )
+        .await?;
+        Ok(result.minimum_ttl)
+    }
+
+    async fn fetch_rrsets(
+        &self,
+        request: &HttpClient,
+        domain: &Domain,
+        record_type: &str,
+    ) -> Result<Vec<RrSet>> {
+        let mut rrsets = Vec::new();
+        // An empty cursor opts into pagination for domains with many RRsets
+        let mut next = Some(self.rrsets_url(domain));
+        let mut query = Some([("type", record_type), ("cursor", "")]);
+        while let Some(url) = next.take() {
+            let mut builder = request.get(url);
+            if let Some(query) = query.take() {
+                builder = builder.query(&query);
+            }
+            let response = self.send(self.authorize(builder)).await?;
+            next = next_link(&response);
+            rrsets.extend(
+                parse_desec_response::<Vec<RrSet>>(
+                    response,
+                    &format!("list deSEC domain ({}) RRsets", domain.name),
+                )
+                .await?,
+            );
+        }
+        Ok(rrsets)
+    }
+
+    async fn patch_rrsets(
+        &self,
+        request: &HttpClient,
+        domain: &Domain,
+        rrsets: &[RrSet],
+    ) -> Result<()> {
+        let response = self
+            .send(self.authorize(request.patch(self.rrsets_url(domain)).json(rrsets)))
+            .await?;
+        parse_desec_response::<Vec<RrSet>>(
+            response,
+            &format!("update deSEC domain ({}) RRsets", domain.name),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn update_domain(
+        &self,
+        request: &HttpClient,
+        domain: &Domain,
+        update: &IpUpdate,
+    ) -> Result<bool> {
+        let minimum_ttl = self.fetch_minimum_ttl(request, domain).await?;
+        let ttl = if domain.ttl < minimum_ttl {
+            warn!(
+                "deSEC domain ({}) TTL {} is below the domain minimum, using {minimum_ttl}",
+                domain.name, domain.ttl
+            );
+            minimum_ttl
+        } else {
+            domain.ttl
+        };
+
+        let mut changed = Vec::new();
+        for (version, addr) in update.iter() {
+            let record_type = match version {
+                IpVersion::V4 => "A",
+                IpVersion::V6 => "AAAA",
+            };
+            let current = self.fetch_rrsets(request, domain, record_type).await?;
+            for subname in &domain.subnames {
+                let desired = desired_rrset(subname, record_type, ttl, addr);
+                if !current.contains(&desired) {
+                    changed.push(desired);
+                }
+            }
+        }
+
+        if changed.is_empty() {
+            debug!("deSEC domain ({}) RRsets already up to date", domain.name);
+            return Ok(false);
+        }
+
+        self.patch_rrsets(request, domain, &changed).await?;
+        info!(
+            "Updated deSEC domain ({}) subnames: {}",
+            domain.name,
+            format_changed(&changed)
+        );
+        Ok(true)
+    }
+
+    fn rrsets_url(&self, domain: &Domain) -> String {
+        format!("{}/domains/{}/rrsets/", self.api_url, domain.name)
+    }
+
+    fn authorize(&self, builder: RequestBuilder) -> RequestBuilder {
+        builder.header(
+            "Authorization",
+            format!("Token {}", self.token.expose_secret()),
+        )
+    }
+
+    /// Sends a request, waiting out deSEC's `Retry-After` throttling.
+    ///
+    /// The client's retry middleware leaves 429 responses to this loop.
+    async fn send(&self, builder: RequestBuilder) -> Result<Response> {
+        let mut attempts = 0;
+        loop {
+            let response = builder
+                .try_clone()
+                .ok_or_else(|| anyhow!("failed to clone deSEC request"))?
+                .send()
+                .await?;
+            if response.status() != StatusCode::TOO_MANY_REQUESTS
+                || attempts >= MAX_THROTTLE_RETRIES
+            {
+                return Ok(response);
+            }
+            let delay = retry_after(&response).unwrap_or(DEFAULT_THROTTLE_DELAY);
+            if delay > MAX_THROTTLE_DELAY {
+                return Err(anyhow!(
+                    "deSEC API throttled requests, retry after {}s",
+                    delay.as_secs()
+                ));
+            }
+            debug!(
+                "deSEC API throttled request, retrying in {}s",
+                delay.as_secs()
+            );
+            time::sleep(delay).await;
+            attempts += 1;
+        }
+    }
+}
+
+fn desired_rrset(subname: &str, record_type: &str, ttl: u32, addr: IpAddr) -> RrSet {
+    RrSet {
+        subname: subname.into(),
+        record_type: record_type.into(),
+        ttl,
+        records: vec![addr.to_string()],
+    }
+}
+
+fn format_changed(rrsets: &[RrSet]) -> String {
+    rrsets
+        .iter()
+        .map(|rrset| {
+            let subname = if rrset.subname.is_empty() {
+                "@"
+            } else {
+                rrset.subname.as_str()
+            };
+            format!("{subname} ({})", rrset.record_type)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+fn next_link(response: &Response) -> Option<String> {
+    let links = response.headers().get(LINK)?.to_str().ok()?;
+    links.split(',').find_map(|link| {
+        let (url, params) = link.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == "rel=\"next\"")
+            .then(|| {
+                url.trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            })
+    })
+}
+
+async fn parse_desec_response<T>(response: Response, action: &str) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let status = response.status();
+    let body = response.text().await?;
+
+    if !status.is_success() {
+        let detail = serde_json::from_str::<DesecError>(&body)
+            .ok()
+            .and_then(|error| error.detail)
+            .filter(|detail| !detail.is_empty())
+            .or_else(|| body_snippet(&body));
+        let detail = detail.map_or_else(String::new, |detail| format!(": {detail}"));
+
+        return Err(anyhow!("failed to {action}: HTTP {status}{detail}"));
+    }
+
+    serde_json::from_str::<T>(&body)
+        .map_err(|error| anyhow!("failed to parse deSEC response for {action}: {error}"))
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[async_trait]
+#[typetag::deserialize(name = "desec")]
+impl Provider for Desec {
+    fn validate_config(&self) -> Result<()> {
+        if self.token.expose_secret().trim().is_empty() {
+            return Err(anyhow!("deSEC token must not be empty"));
+        }
+        if self.domains.is_empty() {
+            return Err(anyhow!("no domains configured for deSEC provider"));
+        }
+        for domain in &self.domains {
+            if domain.name.trim().is_empty() {
+                return Err(anyhow!("deSEC domain names must not be empty"));
+            }
+            if domain.subnames.is_empty() {
+                return Err(anyhow!(
+                    "no subnames configured for deSEC domain ({})",
+                    domain.name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn update(&self, update: IpUpdate, request: HttpClient) -> Result<bool> {
+        let mut changed = false;
+        for domain in &self.domains {
+            changed |= self.update_domain(&request, domain, &update).await?;
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use reqwest_retry::RetryTransientMiddleware;
+    use reqwest_retry::policies::ExponentialBackoff;
+
+    use crate::client::RetryStrategy;
+    use serde_json::json;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_json, header, method, path, query_param},
+    };
+
+    use super::*;
+
+    const UPDATE_BOTH: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+    const UPDATE_V4: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: None,
+    };
+
+    fn provider(api_url: String) -> Desec {
+        Desec {
+            token: "token".into(),
+            domains: smallvec![Domain {
+                name: "example.dedyn.io".into(),
+                subnames: smallvec!["".into(), "www".into()],
+                ttl: 3600,
+            }],
+            api_url,
+        }
+    }
+
+    async fn mount_domain(mock: &MockServer, minimum_ttl: u32) {
+        Mock::given(method("GET"))
+            .and(path("/domains/example.dedyn.io/"))
+            .and(header("Authorization", "Token token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "name": "example.dedyn.io",
+                "minimum_ttl": minimum_ttl,
+            })))
+            .expect(1)
+            .mount(mock)
+            .await;
+    }
+
+    /// A client with the same retry middleware as the real one.
+    fn retrying_http() -> HttpClient {
+        let retry_policy = ExponentialBackoff::builder()
+            .retry_bounds(Duration::from_millis(10), Duration::from_millis(50))
+            .build_with_max_retries(3);
+        ClientBuilder::new(InnerHttpClient::new())
+            .with(RetryTransientMiddleware::new_with_policy_and_strategy(
+                retry_policy,
+                RetryStrategy,
+            ))
+            .build()
+    }
+
+    #[test]
+    fn test_desec_deserializes_from_config() {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+[[providers]]
+type = "desec"
+token = "token"
+
+[[providers.domains]]
+name = "example.dedyn.io"
+subnames = ["", "www"]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.providers.len(), 1);
+        config.providers[0].validate_config().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_desec_bad_token() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/domains/example.dedyn.io/"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+                "detail": "Invalid token."
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_BOTH, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to fetch deSEC domain (example.dedyn.io): HTTP 401 Unauthorized: Invalid token."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_desec_upserts_changed_rrsets_in_bulk() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+        mount_domain(&mock, 3600).await;
+
+        Mock::given(method("GET"))
+            .and(path("/domains/example.dedyn.io/rrsets/"))
+            .and(query_param("type", "A"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                {"subname": "", "type": "A", "ttl": 3600, "records": ["127.0.0.1"]},
+                {"subname": "www", "type": "A", "ttl": 3600, "records": ["192.0.2.1"]}
+            ])))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/domains/example.dedyn.io/rrsets/"))
+            .and(query_param("type", "AAAA"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        Mock::given(method("PATCH"))
+            .and(path("/domains/example.dedyn.io/rrsets/"))
+            .and(header("Authorization", "Token token"))
+            .and(body_json(json!([
+                {"subname": "www", "type": "A", "ttl": 3600, "records": ["127.0.0.1"]},
+                {"subname": "", "type": "AAAA", "ttl": 3600, "records": ["::1"]},
+                {"subname": "www", "type": "AAAA", "ttl": 3600, "records": ["::1"]}
+            ])))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_BOTH, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_desec_skips_unchanged_rrsets() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+        mount_domain(&mock, 3600).await;
+
+        Mock::given(method("GET"))
+            .and(path("/domains/example.dedyn.io/rrsets/"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                {"subname": "", "type": "A", "ttl": 3600, "records": ["127.0.0.1"]},
+                {"subname": "www", "type": "A", "ttl": 3600, "records": ["127.0.0.1"]}
+            ])))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        Mock::given(method("PATCH"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .expect(0)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_V4, http).await.unwrap();
+        assert!(!result);
+    }
+
+    #[tokio::test]
+    async fn test_desec_raises_ttl_to_domain_minimum() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let mut provider = provider(mock.uri());
+        provider.domains[0].ttl = 60;
+        provider.domains[0].subnames = smallvec!["".into()];
+        mount_domain(&mock, 600).await;
+
+        Mock::given(method("GET"))
+            .and(path("/domains/example.dedyn.io/rrsets/"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .mount(&mock)
+            .await;
+
+        Mock::given(method("PATCH"))
+            .and(path("/domains/example.dedyn.io/rrsets/"))
+            .and(body_json(json!([
+                {"subname": "", "type": "A", "ttl": 600, "records": ["127.0.0.1"]}
+            ])))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_V4, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_desec_waits_for_retry_after() {
+        let mock = MockServer::start().await;
+        let http = retrying_http();
+        let mut provider = provider(mock.uri());
+        provider.domains[0].subnames = smallvec!["".into()];
+
+        Mock::given(method("GET"))
+            .and(path("/domains/example.dedyn.io/"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock)
+            .await;
+        mount_domain(&mock, 3600).await;
+
+        Mock::given(method("GET"))
+            .and(path("/domains/example.dedyn.io/rrsets/"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                {"subname": "", "type": "A", "ttl": 3600, "records": ["127.0.0.1"]}
+            ])))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_V4, http).await.unwrap();
+        assert!(!result);
+    }
+
+    #[tokio::test]
+    async fn test_desec_throttling_is_not_retried_by_middleware() {
+        let mock = MockServer::start().await;
+        let http = retrying_http();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/domains/example.dedyn.io/"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+            .expect(u64::from(MAX_THROTTLE_RETRIES) + 1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to fetch deSEC domain (example.dedyn.io): HTTP 429 Too Many Requests"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_desec_retries_server_errors() {
+        let mock = MockServer::start().await;
+        let http = retrying_http();
+        let mut provider = provider(mock.uri());
+        provider.domains[0].subnames = smallvec!["".into()];
+
+        Mock::given(method("GET"))
+            .and(path("/domains/example.dedyn.io/"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock)
+            .await;
+        mount_domain(&mock, 3600).await;
+
+        Mock::given(method("GET"))
+            .and(path("/domains/example.dedyn.io/rrsets/"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                {"subname": "", "type": "A", "ttl": 3600, "records": ["127.0.0.1"]}
+            ])))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_V4, http).await.unwrap();
+        assert!(!result);
+    }
+
+    #[tokio::test]
+    async fn test_desec_rejects_long_retry_after() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/domains/example.dedyn.io/"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "3600"))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "deSEC API throttled requests, retry after 3600s"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_desec_follows_rrset_pagination() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let mut provider = provider(mock.uri());
+        provider.domains[0].subnames = smallvec!["".into()];
+        mount_domain(&mock, 3600).await;
+
+        Mock::given(method("GET"))
+            .and(path("/domains/example.dedyn.io/rrsets/"))
+            .and(query_param("cursor", ""))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header(
+                        "Link",
+                        format!(
+                            "<{}/domains/example.dedyn.io/rrsets/?cursor=next>; rel=\"next\"",
+                            mock.uri()
+                        ),
+                    )
+                    .set_body_json(json!([])),
+            )
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/domains/example.dedyn.io/rrsets/"))
+            .and(query_param("cursor", "next"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                {"subname": "", "type": "A", "ttl": 3600, "records": ["127.0.0.1"]}
+            ])))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_V4, http).await.unwrap();
+        assert!(!result);
+    }
+
+    #[test]
+    fn test_desec_validates_config() {
+        let mut provider = provider(default_api_url());
+        provider.validate_config().unwrap();
+
+        provider.domains[0].subnames = smallvec![];
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "no subnames configured for deSEC domain (example.dedyn.io)"
+        );
+
+        provider.domains = smallvec![];
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "no domains configured for deSEC provider"
+        );
+
+        provider.token = "".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "deSEC token must not be empty"
+        );
+    }
+}
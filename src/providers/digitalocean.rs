@@ -0,0 +1,563 @@
+use std::net::IpAddr;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use compact_str::CompactString;
+use reqwest::Response;
+use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use smallvec::SmallVec;
+
+use crate::ip::{IpUpdate, IpVersion};
+use crate::providers::Provider;
+
+// DigitalOcean rejects record TTLs below 30 seconds
+const MIN_TTL: u32 = 30;
+const RECORDS_PER_PAGE: &str = "200";
+
+/// `DigitalOcean` DNS update provider
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct DigitalOcean {
+    domain: CompactString,
+    api_token: SecretString,
+    domains: SmallVec<[Domain; 2]>,
+    #[serde(default = "default_api_url")]
+    api_url: String,
+}
+
+fn default_api_url() -> String {
+    "https://api.digitalocean.com/v2".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+struct Domain {
+    name: CompactString,
+    #[serde(default = "default_ttl")]
+    ttl: u32,
+}
+
+fn default_ttl() -> u32 {
+    1800
+}
+
+#[derive(Debug, Deserialize)]
+struct DigitalOceanError {
+    message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListRecordsResult {
+    domain_records: Vec<RecordResult>,
+    #[serde(default)]
+    links: Links,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct Links {
+    #[serde(default)]
+    pages: Pages,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct Pages {
+    next: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordResult {
+    id: u64,
+}
+
+impl DigitalOcean {
+    async fn fetch_dns_records(
+        &self,
+        request: &HttpClient,
+        record_type: &str,
+        domain: &Domain,
+    ) -> Result<Vec<RecordResult>> {
+        let mut records = Vec::new();
+        let mut page = 1u32;
+        loop {
+            let response = request
+                .get(format!("{}/domains/{}/records", self.api_url, self.domain))
+                .query(&[("name", domain.name.as_str()), ("type", record_type)])
+                .query(&[
+                    ("page", page.to_string().as_str()),
+                    ("per_page", RECORDS_PER_PAGE),
+                ])
+                .bearer_auth(self.api_token.expose_secret())
+                .send()
+                .await?;
+            let result = parse_digitalocean_response::<ListRecordsResult>(
+                response,
+                &format!("list DigitalOcean DNS records for {}", domain.name),
+            )
+            .await?;
+            records.extend(result.domain_records);
+            if result.links.pages.next.is_none() {
+                return Ok(records);
+            }
+            page += 1;
+        }
+    }
+
+    async fn update_dns_record(
+        &self,
+        request: &HttpClient,
+        record_id: u64,
+        record_type: &str,
+        domain: &Domain,
+        address: &IpAddr,
+    ) -> Result<()> {
+        let response = request
+            .put(format!(
+                "{}/domains/{}/records/{}",
+                self.api_url, self.domain, record_id
+            ))
+            .json(&json!({
+                "type": record_type,
+                "name": self.record_name(domain),
+                "data": address,
+                "ttl": domain.ttl,
+            }))
+            .bearer_auth(self.api_token.expose_secret())
+            .send()
+            .await?;
+        parse_digitalocean_response::<Value>(
+            response,
+            &format!("update DigitalOcean domain ({}) record", domain.name),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn create_dns_record(
+        &self,
+        request: &HttpClient,
+        record_type: &str,
+        domain: &Domain,
+        address: &IpAddr,
+    ) -> Result<()> {
+        let response = request
+            .post(format!("{}/domains/{}/records", self.api_url, self.domain))
+            .json(&json!({
+                "type": record_type,
+                "name": self.record_name(domain),
+                "data": address,
+                "ttl": domain.ttl,
+            }))
+            .bearer_auth(self.api_token.expose_secret())
+            .send()
+            .await?;
+        parse_digitalocean_response::<Value>(
+            response,
+            &format!("create DigitalOcean domain ({}) record", domain.name),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Record name relative to the configured domain, `@` for the apex.
+    fn record_name<'a>(&self, domain: &'a Domain) -> &'a str {
+        match domain
+            .name
+            .strip_suffix(self.domain.as_str())
+            .and_then(|name| name.strip_suffix('.'))
+        {
+            Some(name) if !name.is_empty() => name,
+            _ => "@",
+        }
+    }
+}
+
+async fn parse_digitalocean_response<T>(response: Response, action: &str) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let status = response.status();
+    let body = response.text().await?;
+
+    if !status.is_success() {
+        let detail = serde_json::from_str::<DigitalOceanError>(&body)
+            .ok()
+            .and_then(|error| error.message)
+            .filter(|message| !message.is_empty())
+            .or_else(|| body_snippet(&body));
+        let detail = detail.map_or_else(String::new, |detail| format!(": {detail}"));
+
+        return Err(anyhow!("failed to {action}: HTTP {status}{detail}"));
+    }
+
+    serde_json::from_str::<T>(&body)
+        .map_err(|error| anyhow!("failed to parse DigitalOcean response for {action}: {error}"))
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[async_trait]
+#[typetag::deserialize(name = "digitalocean")]
+impl Provider for DigitalOcean {
+    fn validate_config(&self) -> Result<()> {
+        if self.domain.trim().is_empty() {
+            return Err(anyhow!("DigitalOcean domain must not be empty"));
+        }
+        if self.domains.is_empty() {
+            return Err(anyhow!("no domains configured for DigitalOcean provider"));
+        }
+        for domain in &self.domains {
+            if domain.name != self.domain && !domain.name.ends_with(&format!(".{}", self.domain)) {
+                return Err(anyhow!(
+                    "DigitalOcean domain ({}) is not within {}",
+                    domain.name,
+                    self.domain
+                ));
+            }
+            if domain.ttl < MIN_TTL {
+                return Err(anyhow!(
+                    "DigitalOcean domain ({}) TTL must be at least {MIN_TTL}",
+                    domain.name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn update(&self, update: IpUpdate, request: HttpClient) -> Result<bool> {
+        for domain in &self.domains {
+            for (version, addr) in update.iter() {
+                let record_type = match version {
+                    IpVersion::V4 => "A",
+                    IpVersion::V6 => "AAAA",
+                };
+                if let Some(record) = self
+                    .fetch_dns_records(&request, record_type, domain)
+                    .await?
+                    .first()
+                {
+                    self.update_dns_record(&request, record.id, record_type, domain, &addr)
+                        .await?;
+                } else {
+                    self.create_dns_record(&request, record_type, domain, &addr)
+                        .await?;
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use smallvec::smallvec;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{bearer_token, body_json, method, path, query_param},
+    };
+
+    use super::*;
+
+    const UPDATE_BOTH: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+    const UPDATE_V4: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: None,
+    };
+
+    fn provider(api_url: String, name: &str) -> DigitalOcean {
+        DigitalOcean {
+            domain: "example.com".into(),
+            api_token: "token".into(),
+            domains: smallvec![Domain {
+                name: name.into(),
+                ttl: 300,
+            }],
+            api_url,
+        }
+    }
+
+    #[test]
+    fn test_digitalocean_deserializes_from_config() {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+[[providers]]
+type = "digitalocean"
+domain = "example.com"
+api_token = "token"
+
+[[providers.domains]]
+name = "home.example.com"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.providers.len(), 1);
+        config.providers[0].validate_config().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_digitalocean_bad_token() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri(), "example.com");
+
+        Mock::given(method("GET"))
+            .and(path("/domains/example.com/records"))
+            .and(bearer_token(provider.api_token.expose_secret()))
+            .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+                "id": "unauthorized",
+                "message": "Unable to authenticate you"
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_BOTH, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to list DigitalOcean DNS records for example.com: HTTP 401 Unauthorized: Unable to authenticate you"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_digitalocean_update_both() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri(), "example.com");
+
+        Mock::given(method("GET"))
+            .and(path("/domains/example.com/records"))
+            .and(bearer_token(provider.api_token.expose_secret()))
+            .and(query_param("name", "example.com"))
+            .and(query_param("type", "A"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "domain_records": [
+                    {"id": 1, "type": "A", "name": "@", "data": "192.0.2.1", "ttl": 300}
+                ],
+                "links": {},
+                "meta": {"total": 1}
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/domains/example.com/records"))
+            .and(bearer_token(provider.api_token.expose_secret()))
+            .and(query_param("name", "example.com"))
+            .and(query_param("type", "AAAA"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "domain_records": [
+                    {"id": 2, "type": "AAAA", "name": "@", "data": "2001:db8::1", "ttl": 300}
+                ],
+                "links": {},
+                "meta": {"total": 1}
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        Mock::given(method("PUT"))
+            .and(path("/domains/example.com/records/1"))
+            .and(bearer_token(provider.api_token.expose_secret()))
+            .and(body_json(json!({
+                "type": "A",
+                "name": "@",
+                "data": Ipv4Addr::LOCALHOST.to_string(),
+                "ttl": 300,
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "domain_record": {"id": 1}
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        Mock::given(method("PUT"))
+            .and(path("/domains/example.com/records/2"))
+            .and(bearer_token(provider.api_token.expose_secret()))
+            .and(body_json(json!({
+                "type": "AAAA",
+                "name": "@",
+                "data": Ipv6Addr::LOCALHOST.to_string(),
+                "ttl": 300,
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "domain_record": {"id": 2}
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_BOTH, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_digitalocean_create_subdomain() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri(), "home.example.com");
+
+        Mock::given(method("GET"))
+            .and(path("/domains/example.com/records"))
+            .and(query_param("name", "home.example.com"))
+            .and(query_param("type", "A"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "domain_records": [],
+                "links": {},
+                "meta": {"total": 0}
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/domains/example.com/records"))
+            .and(bearer_token(provider.api_token.expose_secret()))
+            .and(body_json(json!({
+                "type": "A",
+                "name": "home",
+                "data": Ipv4Addr::LOCALHOST.to_string(),
+                "ttl": 300,
+            })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                "domain_record": {"id": 3}
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_V4, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_digitalocean_follows_record_pagination() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri(), "example.com");
+
+        Mock::given(method("GET"))
+            .and(path("/domains/example.com/records"))
+            .and(query_param("page", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "domain_records": [],
+                "links": {"pages": {"next": format!("{}/domains/example.com/records?page=2", mock.uri())}},
+                "meta": {"total": 1}
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/domains/example.com/records"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "domain_records": [
+                    {"id": 4, "type": "A", "name": "@", "data": "192.0.2.1", "ttl": 300}
+                ],
+                "links": {"pages": {"prev": format!("{}/domains/example.com/records?page=1", mock.uri())}},
+                "meta": {"total": 1}
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        Mock::given(method("PUT"))
+            .and(path("/domains/example.com/records/4"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "domain_record": {"id": 4}
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_V4, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_digitalocean_rejects_non_success_http_status() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri(), "example.com");
+
+        Mock::given(method("GET"))
+            .and(path("/domains/example.com/records"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "domain_records": [],
+                "links": {},
+                "meta": {"total": 0}
+            })))
+            .mount(&mock)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/domains/example.com/records"))
+            .respond_with(ResponseTemplate::new(422).set_body_json(json!({
+                "id": "unprocessable_entity",
+                "message": "Data needs to be a valid IP address"
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to create DigitalOcean domain (example.com) record: HTTP 422 Unprocessable Entity: Data needs to be a valid IP address"
+        );
+    }
+
+    #[test]
+    fn test_digitalocean_validates_config() {
+        let mut provider = provider(default_api_url(), "example.org");
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "DigitalOcean domain (example.org) is not within example.com"
+        );
+
+        provider.domains[0].name = "home.example.com".into();
+        provider.domains[0].ttl = 10;
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "DigitalOcean domain (home.example.com) TTL must be at least 30"
+        );
+
+        provider.domains = smallvec![];
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "no domains configured for DigitalOcean provider"
+        );
+    }
+}
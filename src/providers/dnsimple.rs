@@ -0,0 +1,551 @@
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use compact_str::CompactString;
+use reqwest::Response;
+use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use smallvec::SmallVec;
+
+use crate::ip::{IpUpdate, IpVersion};
+use crate::providers::Provider;
+
+/// `DNSimple` update provider
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Dnsimple {
+    api_token: SecretString,
+    account_id: Option<u64>,
+    zone: CompactString,
+    domains: SmallVec<[Domain; 2]>,
+    #[serde(default = "default_api_url")]
+    api_url: String,
+    /// Account ID discovered through `whoami`, shared between clones
+    #[serde(skip)]
+    discovered_account_id: Arc<Mutex<Option<u64>>>,
+}
+
+fn default_api_url() -> String {
+    "https://api.dnsimple.com".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+struct Domain {
+    name: CompactString,
+    #[serde(default = "default_ttl")]
+    ttl: u32,
+}
+
+fn default_ttl() -> u32 {
+    300
+}
+
+#[derive(Debug, Deserialize)]
+struct Data<T> {
+    data: T,
+}
+
+#[derive(Debug, Deserialize)]
+struct Whoami {
+    account: Option<Account>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Account {
+    id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordResult {
+    id: u64,
+    content: String,
+    ttl: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct DnsimpleError {
+    message: Option<String>,
+    #[serde(default)]
+    errors: serde_json::Map<String, Value>,
+}
+
+impl DnsimpleError {
+    fn summary(self) -> Option<String> {
+        let fields = self
+            .errors
+            .into_iter()
+            .flat_map(|(field, messages)| {
+                let messages = match messages {
+                    Value::Array(messages) => messages,
+                    message => vec![message],
+                };
+                messages
+                    .into_iter()
+                    .filter_map(|message| message.as_str().map(str::to_string))
+                    .map(move |message| format!("{field} {message}"))
+            })
+            .collect::<Vec<_>>();
+        match (
+            self.message.filter(|message| !message.is_empty()),
+            fields.is_empty(),
+        ) {
+            (Some(message), true) => Some(message),
+            (Some(message), false) => Some(format!("{message} ({})", fields.join(", "))),
+            (None, false) => Some(fields.join(", ")),
+            (None, true) => None,
+        }
+    }
+}
+
+impl Dnsimple {
+    fn api_url(&self) -> &str {
+        self.api_url.trim_end_matches('/')
+    }
+
+    async fn account_id(&self, request: &HttpClient) -> Result<u64> {
+        if let Some(account_id) = self.account_id {
+            return Ok(account_id);
+        }
+        if let Some(account_id) = self.discovered_account_id.lock().ok().and_then(|id| *id) {
+            return Ok(account_id);
+        }
+
+        let response = request
+            .get(format!("{}/v2/whoami", self.api_url()))
+            .bearer_auth(self.api_token.expose_secret())
+            .send()
+            .await?;
+        let whoami =
+            parse_dnsimple_response::<Data<Whoami>>(response, "look up DNSimple account").await?;
+        // User tokens can access several accounts, so the account must be picked explicitly
+        let account_id = whoami
+            .data
+            .account
+            .map(|account| account.id)
+            .ok_or_else(|| {
+                anyhow!(
+                    "DNSimple token is not an account token, set account_id in the provider config"
+                )
+            })?;
+        if let Ok(mut cached) = self.discovered_account_id.lock() {
+            *cached = Some(account_id);
+        }
+        Ok(account_id)
+    }
+
+    async fn fetch_records(
+        &self,
+        request: &HttpClient,
+        account_id: u64,
+        name: &str,
+        record_type: &str,
+    ) -> Result<Vec<RecordResult>> {
+        let response = request
+            .get(format!(
+                "{}/v2/{account_id}/zones/{}/records",
+                self.api_url(),
+                self.zone
+            ))
+            .query(&[("name", name), ("type", record_type)])
+            .bearer_auth(self.api_token.expose_secret())
+            .send()
+            .await?;
+        let records = parse_dnsimple_response::<Data<Vec<RecordResult>>>(
+            response,
+            &format!("list DNSimple records for {}", self.zone),
+        )
+        .await?;
+        Ok(records.data)
+    }
+
+    async fn update_record(
+        &self,
+        request: &HttpClient,
+        account_id: u64,
+        domain: &Domain,
+        record_type: &str,
+        addr: IpAddr,
+    ) -> Result<bool> {
+        let name = self.record_name(domain);
+        let records = self
+            .fetch_records(request, account_id, name, record_type)
+            .await?;
+        let action = format!(
+            "update DNSimple domain ({}) {record_type} record",
+            domain.name
+        );
+
+        let response = if let Some(record) = records.first() {
+            if record.content.parse::<IpAddr>().ok() == Some(addr) && record.ttl == domain.ttl {
+                return Ok(false);
+            }
+            request
+                .patch(format!(
+                    "{}/v2/{account_id}/zones/{}/records/{}",
+                    self.api_url(),
+                    self.zone,
+                    record.id
+                ))
+                .bearer_auth(self.api_token.expose_secret())
+                .json(&json!({ "content": addr.to_string(), "ttl": domain.ttl }))
+                .send()
+                .await?
+        } else {
+            request
+                .post(format!(
+                    "{}/v2/{account_id}/zones/{}/records",
+                    self.api_url(),
+                    self.zone
+                ))
+                .bearer_auth(self.api_token.expose_secret())
+                .json(&json!({
+                    "name": name,
+                    "type": record_type,
+                    "content": addr.to_string(),
+                    "ttl": domain.ttl,
+                }))
+                .send()
+                .await?
+        };
+        parse_dnsimple_response::<Value>(response, &action).await?;
+        Ok(true)
+    }
+
+    /// Record name relative to the zone, empty for the apex.
+    fn record_name<'a>(&self, domain: &'a Domain) -> &'a str {
+        domain
+            .name
+            .strip_suffix(self.zone.as_str())
+            .and_then(|name| name.strip_suffix('.'))
+            .unwrap_or_default()
+    }
+}
+
+async fn parse_dnsimple_response<T>(response: Response, action: &str) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let status = response.status();
+    let body = response.text().await?;
+
+    if !status.is_success() {
+        let detail = serde_json::from_str::<DnsimpleError>(&body)
+            .ok()
+            .and_then(DnsimpleError::summary)
+            .or_else(|| body_snippet(&body));
+        let detail = detail.map_or_else(String::new, |detail| format!(": {detail}"));
+
+        return Err(anyhow!("failed to {action}: HTTP {status}{detail}"));
+    }
+
+    serde_json::from_str::<T>(&body)
+        .map_err(|error| anyhow!("failed to parse DNSimple response for {action}: {error}"))
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[async_trait]
+#[typetag::deserialize(name = "dnsimple")]
+impl Provider for Dnsimple {
+    fn validate_config(&self) -> Result<()> {
+        if self.api_token.expose_secret().trim().is_empty() {
+            return Err(anyhow!("DNSimple api_token must not be empty"));
+        }
+        if self.zone.trim().is_empty() {
+            return Err(anyhow!("DNSimple zone must not be empty"));
+        }
+        if self.domains.is_empty() {
+            return Err(anyhow!("no domains configured for DNSimple provider"));
+        }
+        for domain in &self.domains {
+            if domain.name != self.zone && !domain.name.ends_with(&format!(".{}", self.zone)) {
+                return Err(anyhow!(
+                    "DNSimple domain ({}) is not within zone ({})",
+                    domain.name,
+                    self.zone
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn update(&self, update: IpUpdate, request: HttpClient) -> Result<bool> {
+        let account_id = self.account_id(&request).await?;
+        let mut changed = false;
+        for domain in &self.domains {
+            for (version, addr) in update.iter() {
+                let record_type = match version {
+                    IpVersion::V4 => "A",
+                    IpVersion::V6 => "AAAA",
+                };
+                changed |= self
+                    .update_record(&request, account_id, domain, record_type, addr)
+                    .await?;
+            }
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use smallvec::smallvec;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_json, header, method, path, query_param},
+    };
+
+    use super::*;
+
+    const UPDATE_BOTH: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+    const UPDATE_V4: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: None,
+    };
+
+    fn provider(api_url: String) -> Dnsimple {
+        Dnsimple {
+            api_token: "token".into(),
+            account_id: None,
+            zone: "example.com".into(),
+            domains: smallvec![
+                Domain {
+                    name: "example.com".into(),
+                    ttl: 300,
+                },
+                Domain {
+                    name: "www.example.com".into(),
+                    ttl: 60,
+                },
+            ],
+            api_url,
+            discovered_account_id: Arc::default(),
+        }
+    }
+
+    async fn mount_whoami(mock: &MockServer, expect: u64) {
+        Mock::given(method("GET"))
+            .and(path("/v2/whoami"))
+            .and(header("authorization", "Bearer token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": {"user": null, "account": {"id": 1010, "email": "admin@example.com"}}
+            })))
+            .expect(expect)
+            .mount(mock)
+            .await;
+    }
+
+    async fn mount_records(mock: &MockServer, name: &str, record_type: &str, records: &Value) {
+        Mock::given(method("GET"))
+            .and(path("/v2/1010/zones/example.com/records"))
+            .and(query_param("name", name))
+            .and(query_param("type", record_type))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": records,
+                "pagination": {"current_page": 1, "per_page": 30, "total_entries": 1, "total_pages": 1}
+            })))
+            .expect(1)
+            .mount(mock)
+            .await;
+    }
+
+    #[test]
+    fn test_dnsimple_deserializes_from_config() {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+[[providers]]
+type = "dnsimple"
+api_token = "token"
+zone = "example.com"
+api_url = "https://api.sandbox.dnsimple.com"
+
+[[providers.domains]]
+name = "www.example.com"
+ttl = 60
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.providers.len(), 1);
+        config.providers[0].validate_config().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dnsimple_updates_and_creates_records() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        mount_whoami(&mock, 1).await;
+        mount_records(
+            &mock,
+            "",
+            "A",
+            &json!([{"id": 1, "zone_id": "example.com", "name": "", "content": "192.0.2.1", "ttl": 300, "type": "A"}]),
+        )
+        .await;
+        mount_records(
+            &mock,
+            "",
+            "AAAA",
+            &json!([{"id": 2, "zone_id": "example.com", "name": "", "content": "::1", "ttl": 300, "type": "AAAA"}]),
+        )
+        .await;
+        mount_records(&mock, "www", "A", &json!([])).await;
+        mount_records(
+            &mock,
+            "www",
+            "AAAA",
+            &json!([{"id": 4, "zone_id": "example.com", "name": "www", "content": "::1", "ttl": 3600, "type": "AAAA"}]),
+        )
+        .await;
+
+        for (id, content, ttl) in [(1, "127.0.0.1", 300), (4, "::1", 60)] {
+            Mock::given(method("PATCH"))
+                .and(path(format!("/v2/1010/zones/example.com/records/{id}")))
+                .and(header("authorization", "Bearer token"))
+                .and(body_json(json!({"content": content, "ttl": ttl})))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({"data": {"id": id}})))
+                .expect(1)
+                .mount(&mock)
+                .await;
+        }
+        Mock::given(method("POST"))
+            .and(path("/v2/1010/zones/example.com/records"))
+            .and(body_json(
+                json!({"name": "www", "type": "A", "content": "127.0.0.1", "ttl": 60}),
+            ))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({"data": {"id": 3}})))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_BOTH, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_dnsimple_caches_account_id() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let mut provider = provider(mock.uri());
+        provider.domains.truncate(1);
+
+        mount_whoami(&mock, 1).await;
+        Mock::given(method("GET"))
+            .and(path("/v2/1010/zones/example.com/records"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [{"id": 1, "name": "", "content": "127.0.0.1", "ttl": 300, "type": "A"}]
+            })))
+            .expect(2)
+            .mount(&mock)
+            .await;
+
+        // Clones share the discovered account ID, as providers are cloned per update
+        assert!(
+            !provider
+                .clone()
+                .update(UPDATE_V4, http.clone())
+                .await
+                .unwrap()
+        );
+        assert!(!provider.update(UPDATE_V4, http).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_dnsimple_user_token_requires_account_id() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/v2/whoami"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": {"user": {"id": 7, "email": "user@example.com"}, "account": null}
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "DNSimple token is not an account token, set account_id in the provider config"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dnsimple_surfaces_errors() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let mut provider = provider(mock.uri());
+        provider.account_id = Some(1010);
+        provider.domains.truncate(1);
+
+        mount_records(&mock, "", "A", &json!([])).await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+                "message": "Validation failed",
+                "errors": {"content": ["can't be blank"]}
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to update DNSimple domain (example.com) A record: HTTP 400 Bad Request: Validation failed (content can't be blank)"
+        );
+    }
+
+    #[test]
+    fn test_dnsimple_validates_config() {
+        let mut provider = provider(default_api_url());
+        provider.validate_config().unwrap();
+
+        provider.domains[1].name = "www.example.org".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "DNSimple domain (www.example.org) is not within zone (example.com)"
+        );
+
+        provider.api_token = "".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "DNSimple api_token must not be empty"
+        );
+    }
+}
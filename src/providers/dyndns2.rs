@@ -0,0 +1,625 @@
+use std::fmt::{self, Display, Formatter};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow};
+use async_trait::async_trait;
+use compact_str::CompactString;
+use reqwest::Response;
+use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use smallvec::SmallVec;
+use url::Url;
+
+use crate::ip::IpUpdate;
+use crate::providers::Provider;
+
+// The dyndns2 protocol caps a single request at 20 hostnames
+const MAX_HOSTNAMES: usize = 20;
+// Clients must wait at least 30 minutes after a server-side failure
+const SERVER_ERROR_BACKOFF: Duration = Duration::from_mins(30);
+
+/// Generic dyndns2 protocol provider
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Dyndns2 {
+    server: String,
+    username: SecretString,
+    password: SecretString,
+    hostnames: SmallVec<[CompactString; 2]>,
+    #[serde(default)]
+    dual_stack: DualStack,
+    #[serde(skip)]
+    state: Arc<Mutex<State>>,
+}
+
+/// How addresses are sent when both versions are updated together
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum DualStack {
+    /// Both addresses in `myip`, comma separated
+    #[default]
+    Combined,
+    /// IPv4 in `myip` and IPv6 in `myipv6`
+    Separate,
+}
+
+/// Shared provider state that outlives a single update
+#[derive(Debug, Default)]
+enum State {
+    #[default]
+    Ready,
+    Halted(Dyndns2Error),
+    BackoffUntil(Instant),
+}
+
+/// dyndns2 return codes that indicate a failed update.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Dyndns2Error {
+    BadAuth,
+    NotDonator,
+    NotFqdn(CompactString),
+    NoHost(CompactString),
+    NumHost,
+    Abuse(CompactString),
+    BadAgent,
+    DnsError,
+    ServerError,
+    Unexpected(String),
+}
+
+impl Dyndns2Error {
+    /// Whether the error requires a configuration change before retrying.
+    fn is_fatal(&self) -> bool {
+        !matches!(
+            self,
+            Self::DnsError | Self::ServerError | Self::Unexpected(_)
+        )
+    }
+
+    fn is_server_error(&self) -> bool {
+        matches!(self, Self::DnsError | Self::ServerError)
+    }
+}
+
+impl Display for Dyndns2Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::BadAuth => f.write_str("dyndns2 authentication failed (badauth)"),
+            Self::NotDonator => f.write_str("dyndns2 option requires a paid account (!donator)"),
+            Self::NotFqdn(host) => {
+                write!(f, "dyndns2 hostname ({host}) is not a valid FQDN (notfqdn)")
+            }
+            Self::NoHost(host) => {
+                write!(
+                    f,
+                    "dyndns2 hostname ({host}) does not exist in this account (nohost)"
+                )
+            }
+            Self::NumHost => f.write_str("dyndns2 request contained too many hostnames (numhost)"),
+            Self::Abuse(host) => {
+                write!(
+                    f,
+                    "dyndns2 hostname ({host}) is blocked for update abuse (abuse)"
+                )
+            }
+            Self::BadAgent => f.write_str("dyndns2 server rejected the user agent (badagent)"),
+            Self::DnsError => f.write_str("dyndns2 server DNS error (dnserr)"),
+            Self::ServerError => f.write_str("dyndns2 server is unavailable (911)"),
+            Self::Unexpected(body) => write!(f, "unexpected dyndns2 response: {body}"),
+        }
+    }
+}
+
+impl std::error::Error for Dyndns2Error {}
+
+impl Dyndns2 {
+    async fn update_hostnames(&self, update: &IpUpdate, request: &HttpClient) -> Result<bool> {
+        let mut params = vec![("hostname", self.hostnames.join(","))];
+        match (self.dual_stack, update.v4, update.v6) {
+            (_, None, None) => {
+                return Err(anyhow!("no IP addresses supplied for dyndns2 update"));
+            }
+            (DualStack::Combined, Some(v4), Some(v6)) => {
+                params.push(("myip", format!("{v4},{v6}")));
+            }
+            (DualStack::Separate, Some(v4), Some(v6)) => {
+                params.push(("myip", v4.to_string()));
+                params.push(("myipv6", v6.to_string()));
+            }
+            (_, Some(v4), None) => params.push(("myip", v4.to_string())),
+            (DualStack::Combined, None, Some(v6)) => params.push(("myip", v6.to_string())),
+            (DualStack::Separate, None, Some(v6)) => params.push(("myipv6", v6.to_string())),
+        }
+
+        let response = request
+            .get(self.update_url()?)
+            .query(&params)
+            .basic_auth(
+                self.username.expose_secret(),
+                Some(self.password.expose_secret()),
+            )
+            .send()
+            .await?;
+
+        parse_update_response(response, &self.hostnames).await
+    }
+
+    fn update_url(&self) -> Result<Url> {
+        let mut url = Url::parse(&self.server)
+            .with_context(|| format!("invalid dyndns2 server URL ({})", self.server))?;
+        if url.path() == "/" {
+            url.set_path("/nic/update");
+        }
+        Ok(url)
+    }
+
+    fn check_state(&self) -> Result<()> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| anyhow!("dyndns2 provider state lock was poisoned"))?;
+        match &*state {
+            State::Ready => Ok(()),
+            State::Halted(error) => Err(anyhow!(
+                "dyndns2 updates halted after previous error, fix the configuration and restart: {error}"
+            )),
+            State::BackoffUntil(until) if Instant::now() < *until => Err(anyhow!(
+                "dyndns2 updates paused for {}s after a server error",
+                until.saturating_duration_since(Instant::now()).as_secs()
+            )),
+            State::BackoffUntil(_) => {
+                *state = State::Ready;
+                Ok(())
+            }
+        }
+    }
+
+    fn record_error(&self, error: &Dyndns2Error) {
+        let next = if error.is_fatal() {
+            State::Halted(error.clone())
+        } else if error.is_server_error() {
+            State::BackoffUntil(Instant::now() + SERVER_ERROR_BACKOFF)
+        } else {
+            return;
+        };
+        if let Ok(mut state) = self.state.lock() {
+            *state = next;
+        }
+    }
+}
+
+#[async_trait]
+#[typetag::deserialize(name = "dyndns2")]
+impl Provider for Dyndns2 {
+    fn validate_config(&self) -> Result<()> {
+        match self.update_url()?.scheme() {
+            "http" | "https" => {}
+            scheme => return Err(anyhow!("dyndns2 server must use http or https: {scheme}")),
+        }
+        if self.username.expose_secret().trim().is_empty() {
+            return Err(anyhow!("dyndns2 username must not be empty"));
+        }
+        if self.password.expose_secret().is_empty() {
+            return Err(anyhow!("dyndns2 password must not be empty"));
+        }
+        if self.hostnames.is_empty() {
+            return Err(anyhow!("no hostnames configured for dyndns2 provider"));
+        }
+        if self.hostnames.len() > MAX_HOSTNAMES {
+            return Err(anyhow!(
+                "dyndns2 provider supports at most {MAX_HOSTNAMES} hostnames"
+            ));
+        }
+        for hostname in &self.hostnames {
+            if hostname.trim().is_empty() {
+                return Err(anyhow!("dyndns2 hostnames must not be empty"));
+            }
+            if hostname.contains(',') {
+                return Err(anyhow!("dyndns2 hostnames must not contain commas"));
+            }
+        }
+        Ok(())
+    }
+
+    async fn update(&self, update: IpUpdate, request: HttpClient) -> Result<bool> {
+        self.validate_config()?;
+        self.check_state()?;
+        let result = self.update_hostnames(&update, &request).await;
+        if let Some(error) = result
+            .as_ref()
+            .err()
+            .and_then(|error| error.downcast_ref::<Dyndns2Error>())
+        {
+            self.record_error(error);
+        }
+        result
+    }
+}
+
+async fn parse_update_response(response: Response, hostnames: &[CompactString]) -> Result<bool> {
+    let status = response.status();
+    let body = response.text().await?;
+
+    if !status.is_success() {
+        let code = body.split_whitespace().next().unwrap_or_default();
+        // Some servers pair return codes with a non-2xx status
+        if let Some(error) = parse_error_code(code, hostnames.first()) {
+            return Err(error.into());
+        }
+        let detail = body_snippet(&body).map_or_else(String::new, |body| format!(": {body}"));
+        return Err(anyhow!(
+            "failed to update dyndns2 hostnames: HTTP {status}{detail}"
+        ));
+    }
+
+    parse_update_response_body(&body, hostnames)
+}
+
+fn parse_update_response_body(body: &str, hostnames: &[CompactString]) -> Result<bool> {
+    let mut lines = body
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .peekable();
+    if lines.peek().is_none() {
+        return Err(Dyndns2Error::Unexpected("empty response".to_string()).into());
+    }
+
+    let mut changed = false;
+    for (index, line) in lines.enumerate() {
+        let code = line.split_whitespace().next().unwrap_or_default();
+        match code {
+            "good" => changed = true,
+            "nochg" => {}
+            code => {
+                let error = parse_error_code(code, hostnames.get(index))
+                    .unwrap_or_else(|| Dyndns2Error::Unexpected(line.to_string()));
+                return Err(error.into());
+            }
+        }
+    }
+    Ok(changed)
+}
+
+fn parse_error_code(code: &str, hostname: Option<&CompactString>) -> Option<Dyndns2Error> {
+    let hostname = || hostname.cloned().unwrap_or_default();
+    match code {
+        "badauth" => Some(Dyndns2Error::BadAuth),
+        "!donator" => Some(Dyndns2Error::NotDonator),
+        "notfqdn" => Some(Dyndns2Error::NotFqdn(hostname())),
+        "nohost" => Some(Dyndns2Error::NoHost(hostname())),
+        "numhost" => Some(Dyndns2Error::NumHost),
+        "abuse" => Some(Dyndns2Error::Abuse(hostname())),
+        "badagent" => Some(Dyndns2Error::BadAgent),
+        "dnserr" => Some(Dyndns2Error::DnsError),
+        "911" => Some(Dyndns2Error::ServerError),
+        _ => None,
+    }
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use smallvec::smallvec;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{basic_auth, method, path, query_param, query_param_is_missing},
+    };
+
+    use super::*;
+
+    const UPDATE_BOTH: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+    const UPDATE_V4: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: None,
+    };
+    const UPDATE_V6: IpUpdate = IpUpdate {
+        v4: None,
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+
+    fn provider(server: &str) -> Dyndns2 {
+        Dyndns2 {
+            server: server.into(),
+            username: "user".into(),
+            password: "pass".into(),
+            hostnames: smallvec!["home.example.com".into(), "nas.example.com".into()],
+            dual_stack: DualStack::Combined,
+            state: Arc::default(),
+        }
+    }
+
+    fn http() -> HttpClient {
+        ClientBuilder::new(InnerHttpClient::new()).build()
+    }
+
+    #[test]
+    fn test_dyndns2_deserializes_from_config() {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+[[providers]]
+type = "dyndns2"
+server = "https://dynupdate.no-ip.com"
+username = "user"
+password = "pass"
+hostnames = ["home.example.com"]
+dual_stack = "separate"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.providers.len(), 1);
+        config.providers[0].validate_config().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dyndns2_update_both_combined() {
+        let mock = MockServer::start().await;
+        let provider = provider(&mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/nic/update"))
+            .and(basic_auth("user", "pass"))
+            .and(query_param("hostname", "home.example.com,nas.example.com"))
+            .and(query_param(
+                "myip",
+                format!("{},{}", Ipv4Addr::LOCALHOST, Ipv6Addr::LOCALHOST),
+            ))
+            .and(query_param_is_missing("myipv6"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(format!("good {0}\ngood {0}\n", Ipv4Addr::LOCALHOST)),
+            )
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_BOTH, http()).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_dyndns2_update_both_separate() {
+        let mock = MockServer::start().await;
+        let mut provider = provider(&mock.uri());
+        provider.dual_stack = DualStack::Separate;
+
+        Mock::given(method("GET"))
+            .and(path("/nic/update"))
+            .and(query_param("myip", Ipv4Addr::LOCALHOST.to_string()))
+            .and(query_param("myipv6", Ipv6Addr::LOCALHOST.to_string()))
+            .respond_with(ResponseTemplate::new(200).set_body_string("good\ngood"))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_BOTH, http()).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_dyndns2_update_ipv6_uses_myip_when_combined() {
+        let mock = MockServer::start().await;
+        let provider = provider(&mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/nic/update"))
+            .and(query_param("myip", Ipv6Addr::LOCALHOST.to_string()))
+            .respond_with(ResponseTemplate::new(200).set_body_string("good\ngood"))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_V6, http()).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_dyndns2_uses_custom_update_path() {
+        let mock = MockServer::start().await;
+        let provider = provider(&format!("{}/custom/update", mock.uri()));
+
+        Mock::given(method("GET"))
+            .and(path("/custom/update"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("good\ngood"))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_V4, http()).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_dyndns2_no_change() {
+        let mock = MockServer::start().await;
+        let provider = provider(&mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/nic/update"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(format!("nochg {0}\nnochg {0}", Ipv4Addr::LOCALHOST)),
+            )
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_V4, http()).await.unwrap();
+        assert!(!result);
+    }
+
+    #[tokio::test]
+    async fn test_dyndns2_bad_auth_halts_further_updates() {
+        let mock = MockServer::start().await;
+        let provider = provider(&mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/nic/update"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("badauth"))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http()).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<Dyndns2Error>(),
+            Some(&Dyndns2Error::BadAuth)
+        );
+
+        // Clones share state, matching how the client dispatches updates
+        let error = provider
+            .clone()
+            .update(UPDATE_V4, http())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "dyndns2 updates halted after previous error, fix the configuration and restart: dyndns2 authentication failed (badauth)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dyndns2_abuse_identifies_hostname() {
+        let mock = MockServer::start().await;
+        let provider = provider(&mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/nic/update"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("good 127.0.0.1\nabuse"))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http()).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<Dyndns2Error>(),
+            Some(&Dyndns2Error::Abuse("nas.example.com".into()))
+        );
+        assert!(matches!(*provider.state.lock().unwrap(), State::Halted(_)));
+    }
+
+    #[tokio::test]
+    async fn test_dyndns2_server_error_backs_off() {
+        let mock = MockServer::start().await;
+        let provider = provider(&mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/nic/update"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("911"))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http()).await.unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<Dyndns2Error>(),
+            Some(&Dyndns2Error::ServerError)
+        );
+
+        let error = provider.update(UPDATE_V4, http()).await.unwrap_err();
+        assert!(
+            error.to_string().starts_with("dyndns2 updates paused for "),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_dyndns2_parses_return_codes() {
+        let hostnames = [CompactString::from("home.example.com")];
+        let cases = [
+            ("!donator", Dyndns2Error::NotDonator),
+            ("notfqdn", Dyndns2Error::NotFqdn("home.example.com".into())),
+            ("nohost", Dyndns2Error::NoHost("home.example.com".into())),
+            ("numhost", Dyndns2Error::NumHost),
+            ("badagent", Dyndns2Error::BadAgent),
+            ("dnserr", Dyndns2Error::DnsError),
+            ("what", Dyndns2Error::Unexpected("what".to_string())),
+        ];
+        for (body, expected) in cases {
+            let error = parse_update_response_body(body, &hostnames).unwrap_err();
+            assert_eq!(error.downcast_ref::<Dyndns2Error>(), Some(&expected));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dyndns2_rejects_non_success_http_status() {
+        let mock = MockServer::start().await;
+        let provider = provider(&mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/nic/update"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("Internal error"))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http()).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to update dyndns2 hostnames: HTTP 500 Internal Server Error: Internal error"
+        );
+        assert!(matches!(*provider.state.lock().unwrap(), State::Ready));
+    }
+
+    #[test]
+    fn test_dyndns2_validates_config() {
+        let mut provider = provider("https://dynupdate.no-ip.com");
+        provider.validate_config().unwrap();
+
+        provider.server = "dynupdate.no-ip.com".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "invalid dyndns2 server URL (dynupdate.no-ip.com)"
+        );
+
+        provider.server = "ftp://example.com".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "dyndns2 server must use http or https: ftp"
+        );
+
+        provider.server = "https://dynupdate.no-ip.com".into();
+        provider.hostnames = smallvec![];
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "no hostnames configured for dyndns2 provider"
+        );
+
+        provider.hostnames = smallvec!["a.example.com,b.example.com".into()];
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "dyndns2 hostnames must not contain commas"
+        );
+    }
+}
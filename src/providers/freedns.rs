@@ -0,0 +1,362 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use compact_str::CompactString;
+use reqwest::Response;
+use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use smallvec::SmallVec;
+
+use crate::config::IpVersions;
+use crate::ip::{IpUpdate, IpVersion};
+use crate::providers::Provider;
+
+/// afraid.org `FreeDNS` update provider using v2 randomized update tokens
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct FreeDns {
+    domains: SmallVec<[Domain; 2]>,
+    #[serde(default = "default_api_url")]
+    api_url: String,
+}
+
+fn default_api_url() -> String {
+    "https://sync.afraid.org".to_string()
+}
+
+/// A host with the update tokens of its A and AAAA records
+#[derive(Debug, Clone, Deserialize, Default)]
+struct Domain {
+    name: CompactString,
+    ipv4_token: Option<SecretString>,
+    ipv6_token: Option<SecretString>,
+}
+
+impl Domain {
+    fn token(&self, version: IpVersion) -> Option<&SecretString> {
+        match version {
+            IpVersion::V4 => self.ipv4_token.as_ref(),
+            IpVersion::V6 => self.ipv6_token.as_ref(),
+        }
+    }
+}
+
+impl FreeDns {
+    async fn update_record(
+        &self,
+        request: &HttpClient,
+        domain: &Domain,
+        token: &SecretString,
+        record_type: &str,
+        address: String,
+    ) -> Result<bool> {
+        // The address is explicit so the source IP of the request doesn't matter
+        let response = request
+            .get(format!(
+                "{}/u/{}/",
+                self.api_url.trim_end_matches('/'),
+                token.expose_secret()
+            ))
+            .query(&[("address", address)])
+            .send()
+            .await?;
+
+        parse_freedns_response(
+            response,
+            &format!("update FreeDNS ({}) {record_type} record", domain.name),
+        )
+        .await
+    }
+}
+
+async fn parse_freedns_response(response: Response, action: &str) -> Result<bool> {
+    let status = response.status();
+    let body = response.text().await?;
+
+    if !status.is_success() {
+        let detail = body_snippet(&body).map_or_else(String::new, |body| format!(": {body}"));
+        return Err(anyhow!("failed to {action}: HTTP {status}{detail}"));
+    }
+
+    let line = body.trim();
+    if line.starts_with("Updated") {
+        return Ok(true);
+    }
+    if line.starts_with("No IP change detected") {
+        return Ok(false);
+    }
+    if let Some(error) = line.strip_prefix("ERROR:") {
+        return Err(anyhow!("failed to {action}: {}", error.trim()));
+    }
+
+    let detail = body_snippet(&body).unwrap_or_else(|| "empty response".to_string());
+    Err(anyhow!("failed to {action}: unexpected response: {detail}"))
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[async_trait]
+#[typetag::deserialize(name = "freedns")]
+impl Provider for FreeDns {
+    fn validate_config(&self) -> Result<()> {
+        if self.domains.is_empty() {
+            return Err(anyhow!("no domains configured for FreeDNS provider"));
+        }
+        for domain in &self.domains {
+            if domain.name.trim().is_empty() {
+                return Err(anyhow!("FreeDNS domain names must not be empty"));
+            }
+            let tokens = [domain.ipv4_token.as_ref(), domain.ipv6_token.as_ref()];
+            if tokens.iter().all(Option::is_none) {
+                return Err(anyhow!(
+                    "FreeDNS domain ({}) must set ipv4_token or ipv6_token",
+                    domain.name
+                ));
+            }
+            if tokens.iter().flatten().any(|token| {
+                let token = token.expose_secret();
+                token.trim().is_empty() || token.contains(['/', '?', '#'])
+            }) {
+                return Err(anyhow!(
+                    "FreeDNS domain ({}) tokens must be the token from the update URL, not the full URL",
+                    domain.name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_versions(&self, versions: IpVersions) -> Result<()> {
+        for version in versions.iter() {
+            if self
+                .domains
+                .iter()
+                .all(|domain| domain.token(version).is_none())
+            {
+                let token = match version {
+                    IpVersion::V4 => "ipv4_token",
+                    IpVersion::V6 => "ipv6_token",
+                };
+                return Err(anyhow!(
+                    "FreeDNS provider has no {token} configured for any domain"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn update(&self, update: IpUpdate, request: HttpClient) -> Result<bool> {
+        let mut changed = false;
+        for domain in &self.domains {
+            for (version, addr) in update.iter() {
+                // Hosts without a token for this version have no such record
+                let Some(token) = domain.token(version) else {
+                    continue;
+                };
+                let record_type = match version {
+                    IpVersion::V4 => "A",
+                    IpVersion::V6 => "AAAA",
+                };
+                changed |= self
+                    .update_record(&request, domain, token, record_type, addr.to_string())
+                    .await?;
+            }
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use smallvec::smallvec;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path, query_param},
+    };
+
+    use super::*;
+
+    const UPDATE_BOTH: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+    const UPDATE_V4: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: None,
+    };
+
+    fn provider(api_url: String) -> FreeDns {
+        FreeDns {
+            domains: smallvec![
+                Domain {
+                    name: "home.mooo.com".into(),
+                    ipv4_token: Some("token-a".into()),
+                    ipv6_token: Some("token-aaaa".into()),
+                },
+                Domain {
+                    name: "v4.mooo.com".into(),
+                    ipv4_token: Some("token-v4".into()),
+                    ipv6_token: None,
+                },
+            ],
+            api_url,
+        }
+    }
+
+    #[test]
+    fn test_freedns_deserializes_from_config() {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+versions = ["v4", "v6"]
+
+[[providers]]
+type = "freedns"
+
+[[providers.domains]]
+name = "home.mooo.com"
+ipv4_token = "token-a"
+ipv6_token = "token-aaaa"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.providers.len(), 1);
+        config.providers[0].validate_config().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_freedns_updates_with_explicit_addresses() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        for (token, address, body) in [
+            (
+                "token-a",
+                "127.0.0.1",
+                "No IP change detected for home.mooo.com with IP 127.0.0.1, skipping update",
+            ),
+            (
+                "token-aaaa",
+                "::1",
+                "Updated 1 host(s) home.mooo.com to ::1 in 0.21 seconds",
+            ),
+            (
+                "token-v4",
+                "127.0.0.1",
+                "No IP change detected for v4.mooo.com with IP 127.0.0.1, skipping update",
+            ),
+        ] {
+            Mock::given(method("GET"))
+                .and(path(format!("/u/{token}/")))
+                .and(query_param("address", address))
+                .respond_with(ResponseTemplate::new(200).set_body_string(body))
+                .expect(1)
+                .mount(&mock)
+                .await;
+        }
+
+        let result = provider.update(UPDATE_BOTH, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_freedns_no_change() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "No IP change detected for home.mooo.com with IP 127.0.0.1, skipping update",
+            ))
+            .expect(2)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_V4, http).await.unwrap();
+        assert!(!result);
+    }
+
+    #[tokio::test]
+    async fn test_freedns_error_response() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "ERROR: Unable to locate this record (changed password recently? deleted?)",
+            ))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to update FreeDNS (home.mooo.com) A record: Unable to locate this record (changed password recently? deleted?)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_freedns_unexpected_response() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<html>maintenance</html>"))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to update FreeDNS (home.mooo.com) A record: unexpected response: <html>maintenance</html>"
+        );
+    }
+
+    #[test]
+    fn test_freedns_validates_config() {
+        let mut provider = provider(default_api_url());
+        provider.validate_config().unwrap();
+
+        provider.domains[1].ipv4_token = Some("https://sync.afraid.org/u/token-v4/".into());
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "FreeDNS domain (v4.mooo.com) tokens must be the token from the update URL, not the full URL"
+        );
+
+        provider.domains[1].ipv4_token = None;
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "FreeDNS domain (v4.mooo.com) must set ipv4_token or ipv6_token"
+        );
+    }
+}
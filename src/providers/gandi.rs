@@ -0,0 +1,393 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use compact_str::CompactString;
+use reqwest::Response;
+use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use serde_json::json;
+use smallvec::SmallVec;
+
+use crate::ip::{IpUpdate, IpVersion};
+use crate::providers::Provider;
+
+// LiveDNS accepts TTLs between 5 minutes and 30 days
+const MIN_TTL: u32 = 300;
+const MAX_TTL: u32 = 2_592_000;
+
+/// Gandi `LiveDNS` update provider
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Gandi {
+    api_token: SecretString,
+    domains: SmallVec<[Domain; 2]>,
+    #[serde(default = "default_api_url")]
+    api_url: String,
+}
+
+fn default_api_url() -> String {
+    "https://api.gandi.net/v5".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+struct Domain {
+    name: CompactString,
+    subdomain: Option<CompactString>,
+    #[serde(default = "default_ttl")]
+    ttl: u32,
+}
+
+fn default_ttl() -> u32 {
+    MIN_TTL
+}
+
+impl Domain {
+    fn record_name(&self) -> &str {
+        self.subdomain.as_deref().unwrap_or("@")
+    }
+
+    fn fqdn(&self) -> String {
+        match self.subdomain.as_deref() {
+            None | Some("@") => self.name.to_string(),
+            Some(subdomain) => format!("{subdomain}.{}", self.name),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GandiError {
+    message: Option<String>,
+    cause: Option<String>,
+    #[serde(default)]
+    errors: Vec<GandiFieldError>,
+}
+
+impl GandiError {
+    fn summary(self) -> Option<String> {
+        let fields = self
+            .errors
+            .iter()
+            .filter_map(|error| match (&error.name, &error.description) {
+                (Some(name), Some(description)) => Some(format!("{name}: {description}")),
+                (None, Some(description)) => Some(description.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let message = self
+            .message
+            .or(self.cause)
+            .filter(|message| !message.is_empty());
+
+        match (message, fields.is_empty()) {
+            (Some(message), true) => Some(message),
+            (Some(message), false) => Some(format!("{message} ({})", fields.join(", "))),
+            (None, false) => Some(fields.join(", ")),
+            (None, true) => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GandiFieldError {
+    name: Option<String>,
+    description: Option<String>,
+}
+
+impl Gandi {
+    async fn replace_rrset(
+        &self,
+        request: &HttpClient,
+        record_type: &str,
+        domain: &Domain,
+        value: String,
+    ) -> Result<()> {
+        let response = request
+            .put(format!(
+                "{}/livedns/domains/{}/records/{}/{}",
+                self.api_url,
+                domain.name,
+                domain.record_name(),
+                record_type
+            ))
+            .json(&json!({
+                "rrset_values": [value],
+                "rrset_ttl": domain.ttl,
+            }))
+            .bearer_auth(self.api_token.expose_secret())
+            .send()
+            .await?;
+        parse_gandi_response(
+            response,
+            &format!(
+                "update Gandi domain ({}) {record_type} record",
+                domain.fqdn()
+            ),
+        )
+        .await
+    }
+}
+
+async fn parse_gandi_response(response: Response, action: &str) -> Result<()> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+
+    let body = response.text().await?;
+    let detail = serde_json::from_str::<GandiError>(&body)
+        .ok()
+        .and_then(GandiError::summary)
+        .or_else(|| body_snippet(&body));
+    let detail = detail.map_or_else(String::new, |detail| format!(": {detail}"));
+
+    Err(anyhow!("failed to {action}: HTTP {status}{detail}"))
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[async_trait]
+#[typetag::deserialize(name = "gandi")]
+impl Provider for Gandi {
+    fn validate_config(&self) -> Result<()> {
+        if self.api_token.expose_secret().trim().is_empty() {
+            return Err(anyhow!("Gandi api_token must not be empty"));
+        }
+        if self.domains.is_empty() {
+            return Err(anyhow!("no domains configured for Gandi provider"));
+        }
+        for domain in &self.domains {
+            if domain.name.trim().is_empty() {
+                return Err(anyhow!("Gandi domain names must not be empty"));
+            }
+            if !(MIN_TTL..=MAX_TTL).contains(&domain.ttl) {
+                return Err(anyhow!(
+                    "Gandi domain ({}) TTL must be between {MIN_TTL} and {MAX_TTL}",
+                    domain.fqdn()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn update(&self, update: IpUpdate, request: HttpClient) -> Result<bool> {
+        for domain in &self.domains {
+            for (version, addr) in update.iter() {
+                let record_type = match version {
+                    IpVersion::V4 => "A",
+                    IpVersion::V6 => "AAAA",
+                };
+                self.replace_rrset(&request, record_type, domain, addr.to_string())
+                    .await?;
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use smallvec::smallvec;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{bearer_token, body_json, method, path},
+    };
+
+    use super::*;
+
+    const UPDATE_BOTH: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+    const UPDATE_V4: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: None,
+    };
+
+    fn provider(api_url: String) -> Gandi {
+        Gandi {
+            api_token: "token".into(),
+            domains: smallvec![
+                Domain {
+                    name: "example.com".into(),
+                    subdomain: None,
+                    ttl: 300,
+                },
+                Domain {
+                    name: "example.com".into(),
+                    subdomain: Some("www".into()),
+                    ttl: 1800,
+                },
+            ],
+            api_url,
+        }
+    }
+
+    #[test]
+    fn test_gandi_deserializes_from_config() {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+[[providers]]
+type = "gandi"
+api_token = "token"
+
+[[providers.domains]]
+name = "example.com"
+subdomain = "www"
+ttl = 600
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.providers.len(), 1);
+        config.providers[0].validate_config().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_gandi_update_both() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        for (name, record_type, value, ttl) in [
+            ("@", "A", Ipv4Addr::LOCALHOST.to_string(), 300),
+            ("@", "AAAA", Ipv6Addr::LOCALHOST.to_string(), 300),
+            ("www", "A", Ipv4Addr::LOCALHOST.to_string(), 1800),
+            ("www", "AAAA", Ipv6Addr::LOCALHOST.to_string(), 1800),
+        ] {
+            Mock::given(method("PUT"))
+                .and(path(format!(
+                    "/livedns/domains/example.com/records/{name}/{record_type}"
+                )))
+                .and(bearer_token("token"))
+                .and(body_json(json!({
+                    "rrset_values": [value],
+                    "rrset_ttl": ttl,
+                })))
+                .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                    "message": "DNS Record Created"
+                })))
+                .expect(1)
+                .mount(&mock)
+                .await;
+        }
+
+        let result = provider.update(UPDATE_BOTH, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_gandi_bad_token() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("PUT"))
+            .and(path("/livedns/domains/example.com/records/@/A"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+                "code": 401,
+                "message": "The server could not verify that you are authorized to access the requested resource.",
+                "object": "HTTPUnauthorized",
+                "cause": "Unauthorized"
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to update Gandi domain (example.com) A record: HTTP 401 Unauthorized: The server could not verify that you are authorized to access the requested resource."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_gandi_surfaces_field_errors() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let mut provider = provider(mock.uri());
+        provider.domains.remove(0);
+
+        Mock::given(method("PUT"))
+            .and(path("/livedns/domains/example.com/records/www/A"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+                "code": 400,
+                "cause": "Bad Request",
+                "message": "Bad Request",
+                "object": "HTTPBadRequest",
+                "errors": [
+                    {"location": "body", "name": "rrset_ttl", "description": "must be at least 300"}
+                ]
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to update Gandi domain (www.example.com) A record: HTTP 400 Bad Request: Bad Request (rrset_ttl: must be at least 300)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_gandi_rejects_non_json_error() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(502).set_body_string("Bad Gateway"))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to update Gandi domain (example.com) A record: HTTP 502 Bad Gateway: Bad Gateway"
+        );
+    }
+
+    #[test]
+    fn test_gandi_validates_config() {
+        let mut provider = provider(default_api_url());
+        provider.validate_config().unwrap();
+
+        provider.domains[1].ttl = 60;
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "Gandi domain (www.example.com) TTL must be between 300 and 2592000"
+        );
+
+        provider.domains = smallvec![];
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "no domains configured for Gandi provider"
+        );
+    }
+}
@@ -0,0 +1,385 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use compact_str::CompactString;
+use reqwest::Response;
+use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use serde_json::json;
+use smallvec::SmallVec;
+
+use crate::ip::{IpUpdate, IpVersion};
+use crate::providers::Provider;
+
+// GoDaddy rejects record TTLs below 600 seconds
+const MIN_TTL: u32 = 600;
+
+/// `GoDaddy` DNS update provider
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct GoDaddy {
+    domain: CompactString,
+    api_key: SecretString,
+    api_secret: SecretString,
+    domains: SmallVec<[Domain; 2]>,
+    #[serde(default = "default_api_url")]
+    api_url: String,
+}
+
+fn default_api_url() -> String {
+    "https://api.godaddy.com".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+struct Domain {
+    #[serde(default = "default_name")]
+    name: CompactString,
+    #[serde(default = "default_ttl")]
+    ttl: u32,
+}
+
+fn default_name() -> CompactString {
+    "@".into()
+}
+
+fn default_ttl() -> u32 {
+    MIN_TTL
+}
+
+#[derive(Debug, Deserialize)]
+struct GoDaddyError {
+    code: Option<String>,
+    message: Option<String>,
+    #[serde(default)]
+    fields: Vec<GoDaddyFieldError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoDaddyFieldError {
+    path: Option<String>,
+    message: Option<String>,
+}
+
+impl GoDaddyError {
+    fn summary(self) -> Option<String> {
+        let mut summary = match (self.code, self.message) {
+            (Some(code), Some(message)) => format!("{code}: {message}"),
+            (Some(detail), None) | (None, Some(detail)) => detail,
+            (None, None) => String::new(),
+        };
+        let fields = self
+            .fields
+            .into_iter()
+            .filter_map(|field| match (field.path, field.message) {
+                (Some(path), Some(message)) => Some(format!("{path}: {message}")),
+                (None, message) => message,
+                (Some(_), None) => None,
+            })
+            .collect::<Vec<_>>();
+        if !fields.is_empty() {
+            if summary.is_empty() {
+                summary = fields.join(", ");
+            } else {
+                summary = format!("{summary} ({})", fields.join(", "));
+            }
+        }
+        (!summary.is_empty()).then_some(summary)
+    }
+}
+
+impl GoDaddy {
+    fn fqdn(&self, domain: &Domain) -> String {
+        if domain.name == "@" {
+            self.domain.to_string()
+        } else {
+            format!("{}.{}", domain.name, self.domain)
+        }
+    }
+
+    async fn replace_records(
+        &self,
+        request: &HttpClient,
+        record_type: &str,
+        domain: &Domain,
+        data: String,
+    ) -> Result<()> {
+        let response = request
+            .put(format!(
+                "{}/v1/domains/{}/records/{record_type}/{}",
+                self.api_url.trim_end_matches('/'),
+                self.domain,
+                domain.name
+            ))
+            .header(
+                reqwest::header::AUTHORIZATION,
+                format!(
+                    "sso-key {}:{}",
+                    self.api_key.expose_secret(),
+                    self.api_secret.expose_secret()
+                ),
+            )
+            .json(&json!([{ "data": data, "ttl": domain.ttl }]))
+            .send()
+            .await?;
+
+        parse_godaddy_response(
+            response,
+            &format!(
+                "update GoDaddy domain ({}) {record_type} record",
+                self.fqdn(domain)
+            ),
+        )
+        .await
+    }
+}
+
+async fn parse_godaddy_response(response: Response, action: &str) -> Result<()> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+
+    let body = response.text().await?;
+    let detail = serde_json::from_str::<GoDaddyError>(&body)
+        .ok()
+        .and_then(GoDaddyError::summary)
+        .or_else(|| body_snippet(&body));
+    let detail = detail.map_or_else(String::new, |detail| format!(": {detail}"));
+
+    Err(anyhow!("failed to {action}: HTTP {status}{detail}"))
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[async_trait]
+#[typetag::deserialize(name = "godaddy")]
+impl Provider for GoDaddy {
+    fn validate_config(&self) -> Result<()> {
+        if self.domain.trim().is_empty() {
+            return Err(anyhow!("GoDaddy domain must not be empty"));
+        }
+        if self.api_key.expose_secret().trim().is_empty() {
+            return Err(anyhow!("GoDaddy api_key must not be empty"));
+        }
+        if self.api_secret.expose_secret().trim().is_empty() {
+            return Err(anyhow!("GoDaddy api_secret must not be empty"));
+        }
+        if self.domains.is_empty() {
+            return Err(anyhow!("no domains configured for GoDaddy provider"));
+        }
+        for domain in &self.domains {
+            if domain.name.trim().is_empty() {
+                return Err(anyhow!(
+                    "GoDaddy record names must not be empty, use @ for the domain apex"
+                ));
+            }
+            if domain.name.contains('/') {
+                return Err(anyhow!(
+                    "GoDaddy record name ({}) must not contain slashes",
+                    domain.name
+                ));
+            }
+            if domain.ttl < MIN_TTL {
+                return Err(anyhow!(
+                    "GoDaddy domain ({}) TTL must be at least {MIN_TTL}",
+                    self.fqdn(domain)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn update(&self, update: IpUpdate, request: HttpClient) -> Result<bool> {
+        for domain in &self.domains {
+            for (version, addr) in update.iter() {
+                let record_type = match version {
+                    IpVersion::V4 => "A",
+                    IpVersion::V6 => "AAAA",
+                };
+                self.replace_records(&request, record_type, domain, addr.to_string())
+                    .await?;
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use smallvec::smallvec;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_json, header, method, path},
+    };
+
+    use super::*;
+
+    const UPDATE_BOTH: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+    const UPDATE_V4: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: None,
+    };
+
+    fn provider(api_url: String) -> GoDaddy {
+        GoDaddy {
+            domain: "example.com".into(),
+            api_key: "key".into(),
+            api_secret: "secret".into(),
+            domains: smallvec![
+                Domain {
+                    name: "@".into(),
+                    ttl: 600,
+                },
+                Domain {
+                    name: "*".into(),
+                    ttl: 3600,
+                },
+            ],
+            api_url,
+        }
+    }
+
+    #[test]
+    fn test_godaddy_deserializes_from_config() {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+[[providers]]
+type = "godaddy"
+domain = "example.com"
+api_key = "key"
+api_secret = "secret"
+
+[[providers.domains]]
+
+[[providers.domains]]
+name = "*.dev"
+ttl = 1800
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.providers.len(), 1);
+        config.providers[0].validate_config().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_godaddy_replaces_records() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        for (record_type, name, data, ttl) in [
+            ("A", "@", "127.0.0.1", 600),
+            ("AAAA", "@", "::1", 600),
+            ("A", "*", "127.0.0.1", 3600),
+            ("AAAA", "*", "::1", 3600),
+        ] {
+            Mock::given(method("PUT"))
+                .and(path(format!(
+                    "/v1/domains/example.com/records/{record_type}/{name}"
+                )))
+                .and(header("authorization", "sso-key key:secret"))
+                .and(body_json(json!([{ "data": data, "ttl": ttl }])))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&mock)
+                .await;
+        }
+
+        let result = provider.update(UPDATE_BOTH, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_godaddy_surfaces_errors() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(422).set_body_json(json!({
+                "code": "INVALID_BODY",
+                "message": "Request body doesn't fulfill schema, see details in `fields`",
+                "fields": [
+                    {"code": "UNEXPECTED_TYPE", "path": "records[0].ttl", "message": "must be at least 600"}
+                ]
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to update GoDaddy domain (example.com) A record: HTTP 422 Unprocessable Entity: INVALID_BODY: Request body doesn't fulfill schema, see details in `fields` (records[0].ttl: must be at least 600)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_godaddy_bad_credentials() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+                "code": "UNABLE_TO_AUTHENTICATE",
+                "message": "Unable to authenticate"
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to update GoDaddy domain (example.com) A record: HTTP 401 Unauthorized: UNABLE_TO_AUTHENTICATE: Unable to authenticate"
+        );
+    }
+
+    #[test]
+    fn test_godaddy_validates_config() {
+        let mut provider = provider(default_api_url());
+        provider.validate_config().unwrap();
+
+        provider.domains[1].ttl = 300;
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "GoDaddy domain (*.example.com) TTL must be at least 600"
+        );
+
+        provider.domains[1].name = "".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "GoDaddy record names must not be empty, use @ for the domain apex"
+        );
+    }
+}
@@ -0,0 +1,415 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use compact_str::CompactString;
+use reqwest::Response;
+use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use smallvec::SmallVec;
+
+use crate::config::IpVersions;
+use crate::ip::{IpUpdate, IpVersion};
+use crate::providers::Provider;
+
+/// Hurricane Electric (dns.he.net) dynamic DNS update provider
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct HurricaneElectric {
+    domains: SmallVec<[Domain; 2]>,
+    #[serde(default = "default_api_url")]
+    api_url: String,
+}
+
+fn default_api_url() -> String {
+    "https://dyn.dns.he.net".to_string()
+}
+
+/// A dynamic hostname with its per-record DDNS keys
+#[derive(Debug, Clone, Deserialize, Default)]
+struct Domain {
+    name: CompactString,
+    ipv4_key: Option<SecretString>,
+    ipv6_key: Option<SecretString>,
+}
+
+impl Domain {
+    fn key(&self, version: IpVersion) -> Option<&SecretString> {
+        match version {
+            IpVersion::V4 => self.ipv4_key.as_ref(),
+            IpVersion::V6 => self.ipv6_key.as_ref(),
+        }
+    }
+}
+
+impl HurricaneElectric {
+    fn update_url(&self) -> String {
+        format!("{}/nic/update", self.api_url.trim_end_matches('/'))
+    }
+
+    async fn update_record(
+        &self,
+        request: &HttpClient,
+        domain: &Domain,
+        key: &SecretString,
+        record_type: &str,
+        ip: String,
+    ) -> Result<bool> {
+        let response = request
+            .get(self.update_url())
+            .query(&[
+                ("hostname", domain.name.as_str()),
+                ("password", key.expose_secret()),
+                ("myip", ip.as_str()),
+            ])
+            .send()
+            .await?;
+
+        parse_he_response(
+            response,
+            &format!(
+                "update Hurricane Electric ({}) {record_type} record",
+                domain.name
+            ),
+        )
+        .await
+    }
+}
+
+async fn parse_he_response(response: Response, action: &str) -> Result<bool> {
+    let status = response.status();
+    let body = response.text().await?;
+    let line = body.trim();
+    let code = line.split_whitespace().next().unwrap_or_default();
+
+    match code {
+        "good" if status.is_success() => return Ok(true),
+        "nochg" if status.is_success() => return Ok(false),
+        _ => {}
+    }
+
+    let detail = match code {
+        "badauth" => "authentication failed, check the hostname and its DDNS key (badauth)",
+        "nohost" => "hostname does not exist or is not enabled for dynamic DNS (nohost)",
+        "notfqdn" => "hostname is not a valid FQDN (notfqdn)",
+        "abuse" => "updates are blocked for abuse, slow down the update interval (abuse)",
+        "badagent" => "user agent was rejected (badagent)",
+        "911" => "server error, retry later (911)",
+        "dnserr" => "DNS error on the server, retry later (dnserr)",
+        _ => {
+            let detail = body_snippet(&body).map_or_else(String::new, |body| format!(": {body}"));
+            return Err(anyhow!("failed to {action}: HTTP {status}{detail}"));
+        }
+    };
+
+    Err(anyhow!("failed to {action}: {detail}"))
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[async_trait]
+#[typetag::deserialize(name = "he")]
+impl Provider for HurricaneElectric {
+    fn validate_config(&self) -> Result<()> {
+        if self.domains.is_empty() {
+            return Err(anyhow!(
+                "no domains configured for Hurricane Electric provider"
+            ));
+        }
+        for domain in &self.domains {
+            if domain.name.trim().is_empty() {
+                return Err(anyhow!("Hurricane Electric domain names must not be empty"));
+            }
+            let keys = [domain.ipv4_key.as_ref(), domain.ipv6_key.as_ref()];
+            if keys.iter().all(Option::is_none) {
+                return Err(anyhow!(
+                    "Hurricane Electric domain ({}) must set ipv4_key or ipv6_key",
+                    domain.name
+                ));
+            }
+            if keys
+                .iter()
+                .flatten()
+                .any(|key| key.expose_secret().trim().is_empty())
+            {
+                return Err(anyhow!(
+                    "Hurricane Electric domain ({}) keys must not be empty",
+                    domain.name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_versions(&self, versions: IpVersions) -> Result<()> {
+        for version in versions.iter() {
+            if self
+                .domains
+                .iter()
+                .all(|domain| domain.key(version).is_none())
+            {
+                let key = match version {
+                    IpVersion::V4 => "ipv4_key",
+                    IpVersion::V6 => "ipv6_key",
+                };
+                return Err(anyhow!(
+                    "Hurricane Electric provider has no {key} configured for any domain"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn update(&self, update: IpUpdate, request: HttpClient) -> Result<bool> {
+        let mut changed = false;
+        for domain in &self.domains {
+            for (version, addr) in update.iter() {
+                // Hostnames without a key for this version have no such record
+                let Some(key) = domain.key(version) else {
+                    continue;
+                };
+                let record_type = match version {
+                    IpVersion::V4 => "A",
+                    IpVersion::V6 => "AAAA",
+                };
+                changed |= self
+                    .update_record(&request, domain, key, record_type, addr.to_string())
+                    .await?;
+            }
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use smallvec::smallvec;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path, query_param},
+    };
+
+    use super::*;
+
+    const UPDATE_BOTH: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+    const UPDATE_V4: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: None,
+    };
+
+    fn provider(api_url: String) -> HurricaneElectric {
+        HurricaneElectric {
+            domains: smallvec![
+                Domain {
+                    name: "dyn.example.com".into(),
+                    ipv4_key: Some("key-a".into()),
+                    ipv6_key: Some("key-aaaa".into()),
+                },
+                Domain {
+                    name: "v4.example.com".into(),
+                    ipv4_key: Some("key-v4".into()),
+                    ipv6_key: None,
+                },
+            ],
+            api_url,
+        }
+    }
+
+    #[test]
+    fn test_he_deserializes_from_config() {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+versions = ["v4", "v6"]
+
+[[providers]]
+type = "he"
+
+[[providers.domains]]
+name = "dyn.example.com"
+ipv4_key = "key-a"
+ipv6_key = "key-aaaa"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.providers.len(), 1);
+        config.providers[0].validate_config().unwrap();
+    }
+
+    #[test]
+    fn test_he_rejects_versions_without_keys() {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+versions = ["v4", "v6"]
+
+[[providers]]
+type = "he"
+
+[[providers.domains]]
+name = "dyn.example.com"
+ipv4_key = "key-a"
+ipv6_key = "key-aaaa"
+"#,
+        )
+        .unwrap();
+        let mut provider = provider(default_api_url());
+        for domain in &mut provider.domains {
+            domain.ipv6_key = None;
+        }
+
+        assert_eq!(
+            provider
+                .validate_versions(config.versions)
+                .unwrap_err()
+                .to_string(),
+            "Hurricane Electric provider has no ipv6_key configured for any domain"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_he_updates_records_with_per_version_keys() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        for (hostname, key, ip, body) in [
+            ("dyn.example.com", "key-a", "127.0.0.1", "good 127.0.0.1"),
+            ("dyn.example.com", "key-aaaa", "::1", "nochg ::1"),
+            ("v4.example.com", "key-v4", "127.0.0.1", "nochg 127.0.0.1"),
+        ] {
+            Mock::given(method("GET"))
+                .and(path("/nic/update"))
+                .and(query_param("hostname", hostname))
+                .and(query_param("password", key))
+                .and(query_param("myip", ip))
+                .respond_with(ResponseTemplate::new(200).set_body_string(body))
+                .expect(1)
+                .mount(&mock)
+                .await;
+        }
+
+        let result = provider.update(UPDATE_BOTH, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_he_nochg_reports_unchanged() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/nic/update"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("nochg 127.0.0.1"))
+            .expect(2)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_V4, http).await.unwrap();
+        assert!(!result);
+    }
+
+    #[tokio::test]
+    async fn test_he_badauth() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/nic/update"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("badauth"))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to update Hurricane Electric (dyn.example.com) A record: authentication failed, check the hostname and its DDNS key (badauth)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_he_dnserr() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/nic/update"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("dnserr"))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to update Hurricane Electric (dyn.example.com) A record: DNS error on the server, retry later (dnserr)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_he_unexpected_response() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/nic/update"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("Internal Server Error"))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to update Hurricane Electric (dyn.example.com) A record: HTTP 500 Internal Server Error: Internal Server Error"
+        );
+    }
+
+    #[test]
+    fn test_he_validates_config() {
+        let mut provider = provider(default_api_url());
+        provider.validate_config().unwrap();
+
+        provider.domains[1].ipv4_key = None;
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "Hurricane Electric domain (v4.example.com) must set ipv4_key or ipv6_key"
+        );
+
+        provider.domains[1].ipv6_key = Some(" ".into());
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "Hurricane Electric domain (v4.example.com) keys must not be empty"
+        );
+    }
+}
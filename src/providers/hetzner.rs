@@ -0,0 +1,598 @@
+use std::net::IpAddr;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use compact_str::CompactString;
+use reqwest::Response;
+use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use smallvec::SmallVec;
+
+use crate::ip::{IpUpdate, IpVersion};
+use crate::providers::Provider;
+
+const AUTH_HEADER: &str = "Auth-API-Token";
+const RECORDS_PER_PAGE: &str = "100";
+
+/// Hetzner DNS update provider
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Hetzner {
+    zone: CompactString,
+    api_token: SecretString,
+    domains: SmallVec<[Domain; 2]>,
+    #[serde(default = "default_api_url")]
+    api_url: String,
+}
+
+fn default_api_url() -> String {
+    "https://dns.hetzner.com/api/v1".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+struct Domain {
+    name: CompactString,
+    ttl: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct RecordBody<'a> {
+    zone_id: &'a str,
+    #[serde(rename = "type")]
+    record_type: &'a str,
+    name: &'a str,
+    value: &'a IpAddr,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ttl: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HetznerErrorResponse {
+    error: Option<HetznerError>,
+    message: Option<String>,
+}
+
+impl HetznerErrorResponse {
+    fn message(self) -> Option<String> {
+        self.error
+            .and_then(|error| error.message)
+            .or(self.message)
+            .filter(|message| !message.is_empty())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct HetznerError {
+    message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ZonesResult {
+    zones: Vec<ZoneResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ZoneResult {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordsResult {
+    records: Vec<RecordResult>,
+    meta: Option<Meta>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Meta {
+    pagination: Pagination,
+}
+
+#[derive(Debug, Deserialize)]
+struct Pagination {
+    page: u32,
+    last_page: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordResult {
+    id: String,
+    #[serde(rename = "type")]
+    record_type: String,
+    name: String,
+}
+
+impl Hetzner {
+    async fn fetch_zone_id(&self, request: &HttpClient) -> Result<String> {
+        let response = request
+            .get(format!("{}/zones", self.api_url))
+            .query(&[("name", &self.zone)])
+            .header(AUTH_HEADER, self.api_token.expose_secret())
+            .send()
+            .await?;
+        let zones = parse_hetzner_response::<ZonesResult>(response, "list Hetzner zones").await?;
+        zones
+            .zones
+            .into_iter()
+            .find(|zone| zone.name.eq_ignore_ascii_case(&self.zone))
+            .map(|zone| zone.id)
+            .ok_or(anyhow!("failed to find a matching Hetzner zone"))
+    }
+
+    async fn fetch_dns_records(
+        &self,
+        request: &HttpClient,
+        zone_id: &str,
+    ) -> Result<Vec<RecordResult>> {
+        let mut records = Vec::new();
+        let mut page = 1u32;
+        loop {
+            let response = request
+                .get(format!("{}/records", self.api_url))
+                .query(&[("zone_id", zone_id)])
+                .query(&[
+                    ("page", page.to_string().as_str()),
+                    ("per_page", RECORDS_PER_PAGE),
+                ])
+                .header(AUTH_HEADER, self.api_token.expose_secret())
+                .send()
+                .await?;
+            let result = parse_hetzner_response::<RecordsResult>(
+                response,
+                &format!("list Hetzner DNS records for {}", self.zone),
+            )
+            .await?;
+            records.extend(result.records);
+            match result.meta {
+                Some(meta) if meta.pagination.page < meta.pagination.last_page => page += 1,
+                _ => return Ok(records),
+            }
+        }
+    }
+
+    async fn update_dns_record(
+        &self,
+        request: &HttpClient,
+        record_id: &str,
+        body: &RecordBody<'_>,
+        domain: &Domain,
+    ) -> Result<()> {
+        let response = request
+            .put(format!("{}/records/{}", self.api_url, record_id))
+            .json(body)
+            .header(AUTH_HEADER, self.api_token.expose_secret())
+            .send()
+            .await?;
+        parse_hetzner_response::<Value>(
+            response,
+            &format!("update Hetzner domain ({}) record", domain.name),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn create_dns_record(
+        &self,
+        request: &HttpClient,
+        body: &RecordBody<'_>,
+        domain: &Domain,
+    ) -> Result<()> {
+        let response = request
+            .post(format!("{}/records", self.api_url))
+            .json(body)
+            .header(AUTH_HEADER, self.api_token.expose_secret())
+            .send()
+            .await?;
+        parse_hetzner_response::<Value>(
+            response,
+            &format!("create Hetzner domain ({}) record", domain.name),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Record name relative to the zone, `@` for the apex.
+    fn record_name<'a>(&self, domain: &'a Domain) -> &'a str {
+        match domain
+            .name
+            .strip_suffix(self.zone.as_str())
+            .and_then(|name| name.strip_suffix('.'))
+        {
+            Some(name) if !name.is_empty() => name,
+            _ => "@",
+        }
+    }
+}
+
+async fn parse_hetzner_response<T>(response: Response, action: &str) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let status = response.status();
+    let body = response.text().await?;
+
+    if !status.is_success() {
+        let detail = serde_json::from_str::<HetznerErrorResponse>(&body)
+            .ok()
+            .and_then(HetznerErrorResponse::message)
+            .or_else(|| body_snippet(&body));
+        let detail = detail.map_or_else(String::new, |detail| format!(": {detail}"));
+
+        return Err(anyhow!("failed to {action}: HTTP {status}{detail}"));
+    }
+
+    serde_json::from_str::<T>(&body)
+        .map_err(|error| anyhow!("failed to parse Hetzner response for {action}: {error}"))
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[async_trait]
+#[typetag::deserialize(name = "hetzner")]
+impl Provider for Hetzner {
+    fn validate_config(&self) -> Result<()> {
+        if self.zone.trim().is_empty() {
+            return Err(anyhow!("Hetzner zone must not be empty"));
+        }
+        if self.domains.is_empty() {
+            return Err(anyhow!("no domains configured for Hetzner provider"));
+        }
+        for domain in &self.domains {
+            if domain.name != self.zone && !domain.name.ends_with(&format!(".{}", self.zone)) {
+                return Err(anyhow!(
+                    "Hetzner domain ({}) is not within zone ({})",
+                    domain.name,
+                    self.zone
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn update(&self, update: IpUpdate, request: HttpClient) -> Result<bool> {
+        let zone_id = self.fetch_zone_id(&request).await?;
+        let records = self.fetch_dns_records(&request, &zone_id).await?;
+        for domain in &self.domains {
+            let name = self.record_name(domain);
+            for (version, addr) in update.iter() {
+                let record_type = match version {
+                    IpVersion::V4 => "A",
+                    IpVersion::V6 => "AAAA",
+                };
+                let body = RecordBody {
+                    zone_id: &zone_id,
+                    record_type,
+                    name,
+                    value: &addr,
+                    ttl: domain.ttl,
+                };
+                if let Some(record) = records
+                    .iter()
+                    .find(|record| record.record_type == record_type && record.name == name)
+                {
+                    self.update_dns_record(&request, &record.id, &body, domain)
+                        .await?;
+                } else {
+                    self.create_dns_record(&request, &body, domain).await?;
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use serde_json::json;
+    use smallvec::smallvec;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_json, header, method, path, query_param},
+    };
+
+    use super::*;
+
+    const ZONE_ID: &str = "HBqnGJYm4Q4L3ZrDxGCExy";
+
+    const UPDATE_BOTH: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+    const UPDATE_V4: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: None,
+    };
+
+    fn provider(api_url: String) -> Hetzner {
+        Hetzner {
+            zone: "example.com".into(),
+            api_token: "token".into(),
+            domains: smallvec![
+                Domain {
+                    name: "example.com".into(),
+                    ttl: Some(300),
+                },
+                Domain {
+                    name: "home.example.com".into(),
+                    ttl: None,
+                },
+            ],
+            api_url,
+        }
+    }
+
+    async fn mount_zone(mock: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path("/zones"))
+            .and(header(AUTH_HEADER, "token"))
+            .and(query_param("name", "example.com"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "zones": [{"id": ZONE_ID, "name": "example.com"}],
+                "meta": {"pagination": {"page": 1, "per_page": 100, "last_page": 1, "total_entries": 1}}
+            })))
+            .expect(1)
+            .mount(mock)
+            .await;
+    }
+
+    #[test]
+    fn test_hetzner_deserializes_from_config() {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+[[providers]]
+type = "hetzner"
+zone = "example.com"
+api_token = "token"
+
+[[providers.domains]]
+name = "home.example.com"
+ttl = 60
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.providers.len(), 1);
+        config.providers[0].validate_config().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_hetzner_bad_token() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/zones"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+                "message": "Invalid authentication credentials"
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_BOTH, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to list Hetzner zones: HTTP 401 Unauthorized: Invalid authentication credentials"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hetzner_no_matching_zone() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/zones"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "zones": [{"id": ZONE_ID, "name": "example.org"}]
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_BOTH, http).await.unwrap_err();
+        assert_eq!(error.to_string(), "failed to find a matching Hetzner zone");
+    }
+
+    #[tokio::test]
+    async fn test_hetzner_update_and_create() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+        mount_zone(&mock).await;
+
+        Mock::given(method("GET"))
+            .and(path("/records"))
+            .and(header(AUTH_HEADER, "token"))
+            .and(query_param("zone_id", ZONE_ID))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "records": [
+                    {"id": "apex-a", "type": "A", "name": "@", "value": "192.0.2.1", "zone_id": ZONE_ID},
+                    {"id": "apex-mx", "type": "MX", "name": "@", "value": "10 mail", "zone_id": ZONE_ID},
+                    {"id": "home-aaaa", "type": "AAAA", "name": "home", "value": "2001:db8::1", "zone_id": ZONE_ID}
+                ]
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        Mock::given(method("PUT"))
+            .and(path("/records/apex-a"))
+            .and(header(AUTH_HEADER, "token"))
+            .and(body_json(json!({
+                "zone_id": ZONE_ID,
+                "type": "A",
+                "name": "@",
+                "value": Ipv4Addr::LOCALHOST.to_string(),
+                "ttl": 300,
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"record": {}})))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/records"))
+            .and(body_json(json!({
+                "zone_id": ZONE_ID,
+                "type": "AAAA",
+                "name": "@",
+                "value": Ipv6Addr::LOCALHOST.to_string(),
+                "ttl": 300,
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"record": {}})))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/records"))
+            .and(body_json(json!({
+                "zone_id": ZONE_ID,
+                "type": "A",
+                "name": "home",
+                "value": Ipv4Addr::LOCALHOST.to_string(),
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"record": {}})))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        Mock::given(method("PUT"))
+            .and(path("/records/home-aaaa"))
+            .and(body_json(json!({
+                "zone_id": ZONE_ID,
+                "type": "AAAA",
+                "name": "home",
+                "value": Ipv6Addr::LOCALHOST.to_string(),
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"record": {}})))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_BOTH, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_hetzner_follows_record_pagination() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let mut provider = provider(mock.uri());
+        provider.domains.truncate(1);
+        mount_zone(&mock).await;
+
+        Mock::given(method("GET"))
+            .and(path("/records"))
+            .and(query_param("page", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "records": [],
+                "meta": {"pagination": {"page": 1, "per_page": 100, "last_page": 2, "total_entries": 1}}
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/records"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "records": [
+                    {"id": "apex-a", "type": "A", "name": "@", "value": "192.0.2.1", "zone_id": ZONE_ID}
+                ],
+                "meta": {"pagination": {"page": 2, "per_page": 100, "last_page": 2, "total_entries": 1}}
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        Mock::given(method("PUT"))
+            .and(path("/records/apex-a"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"record": {}})))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_V4, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_hetzner_rejects_non_success_http_status() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let mut provider = provider(mock.uri());
+        provider.domains.truncate(1);
+        mount_zone(&mock).await;
+
+        Mock::given(method("GET"))
+            .and(path("/records"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"records": []})))
+            .mount(&mock)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/records"))
+            .respond_with(ResponseTemplate::new(422).set_body_json(json!({
+                "record": {},
+                "error": {"message": "invalid value", "code": 422}
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to create Hetzner domain (example.com) record: HTTP 422 Unprocessable Entity: invalid value"
+        );
+    }
+
+    #[test]
+    fn test_hetzner_validates_config() {
+        let mut provider = provider(default_api_url());
+        provider.validate_config().unwrap();
+
+        provider.domains[1].name = "home.example.org".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "Hetzner domain (home.example.org) is not within zone (example.com)"
+        );
+
+        provider.domains = smallvec![];
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "no domains configured for Hetzner provider"
+        );
+    }
+}
@@ -0,0 +1,555 @@
+use std::net::IpAddr;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use compact_str::CompactString;
+use reqwest::Response;
+use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use smallvec::SmallVec;
+use tracing::warn;
+
+use crate::ip::{IpUpdate, IpVersion};
+use crate::providers::Provider;
+
+// Linode rounds any other TTL up to the next of these, 0 uses the domain default
+const ALLOWED_TTLS: [u32; 15] = [
+    0, 30, 120, 300, 3600, 7200, 14400, 28800, 57600, 86400, 172_800, 345_600, 604_800, 1_209_600,
+    2_419_200,
+];
+const PAGE_SIZE: &str = "500";
+
+/// Linode DNS Manager update provider
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Linode {
+    domain: CompactString,
+    api_token: SecretString,
+    domains: SmallVec<[Domain; 2]>,
+    #[serde(default = "default_api_url")]
+    api_url: String,
+}
+
+fn default_api_url() -> String {
+    "https://api.linode.com/v4".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+struct Domain {
+    name: CompactString,
+    #[serde(default = "default_ttl")]
+    ttl: u32,
+}
+
+fn default_ttl() -> u32 {
+    300
+}
+
+#[derive(Debug, Deserialize)]
+struct LinodeErrors {
+    #[serde(default)]
+    errors: Vec<LinodeError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LinodeError {
+    reason: Option<String>,
+    field: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Paginated<T> {
+    data: Vec<T>,
+    page: u32,
+    pages: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct DomainResult {
+    id: u64,
+    domain: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordResult {
+    id: u64,
+    #[serde(rename = "type")]
+    kind: String,
+    name: String,
+}
+
+/// Smallest allowed TTL at or above `ttl`, as Linode would store it.
+fn round_ttl(ttl: u32) -> Option<u32> {
+    ALLOWED_TTLS.iter().copied().find(|allowed| *allowed >= ttl)
+}
+
+impl Linode {
+    async fn fetch_all<T>(&self, request: &HttpClient, url: &str, action: &str) -> Result<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let mut items = Vec::new();
+        let mut page = 1u32;
+        loop {
+            let response = request
+                .get(url)
+                .query(&[
+                    ("page", page.to_string().as_str()),
+                    ("page_size", PAGE_SIZE),
+                ])
+                .bearer_auth(self.api_token.expose_secret())
+                .send()
+                .await?;
+            let result = parse_linode_response::<Paginated<T>>(response, action).await?;
+            items.extend(result.data);
+            if result.page >= result.pages {
+                return Ok(items);
+            }
+            page = result.page + 1;
+        }
+    }
+
+    async fn fetch_domain_id(&self, request: &HttpClient) -> Result<u64> {
+        let domains = self
+            .fetch_all::<DomainResult>(
+                request,
+                &format!("{}/domains", self.api_url),
+                "list Linode domains",
+            )
+            .await?;
+        domains
+            .into_iter()
+            .find(|domain| domain.domain.eq_ignore_ascii_case(&self.domain))
+            .map(|domain| domain.id)
+            .ok_or_else(|| anyhow!("Linode domain ({}) was not found", self.domain))
+    }
+
+    async fn update_dns_record(
+        &self,
+        request: &HttpClient,
+        domain_id: u64,
+        record_id: u64,
+        domain: &Domain,
+        address: &IpAddr,
+    ) -> Result<()> {
+        let response = request
+            .put(format!(
+                "{}/domains/{domain_id}/records/{record_id}",
+                self.api_url
+            ))
+            .json(&json!({
+                "target": address,
+                "ttl_sec": round_ttl(domain.ttl),
+            }))
+            .bearer_auth(self.api_token.expose_secret())
+            .send()
+            .await?;
+        parse_linode_response::<Value>(
+            response,
+            &format!("update Linode domain ({}) record", domain.name),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn create_dns_record(
+        &self,
+        request: &HttpClient,
+        domain_id: u64,
+        record_type: &str,
+        domain: &Domain,
+        address: &IpAddr,
+    ) -> Result<()> {
+        let response = request
+            .post(format!("{}/domains/{domain_id}/records", self.api_url))
+            .json(&json!({
+                "type": record_type,
+                "name": self.record_name(domain),
+                "target": address,
+                "ttl_sec": round_ttl(domain.ttl),
+            }))
+            .bearer_auth(self.api_token.expose_secret())
+            .send()
+            .await?;
+        parse_linode_response::<Value>(
+            response,
+            &format!("create Linode domain ({}) record", domain.name),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Record name relative to the configured domain, empty for the apex.
+    fn record_name<'a>(&self, domain: &'a Domain) -> &'a str {
+        domain
+            .name
+            .strip_suffix(self.domain.as_str())
+            .and_then(|name| name.strip_suffix('.'))
+            .unwrap_or_default()
+    }
+}
+
+async fn parse_linode_response<T>(response: Response, action: &str) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let status = response.status();
+    let body = response.text().await?;
+
+    if !status.is_success() {
+        let detail = serde_json::from_str::<LinodeErrors>(&body)
+            .ok()
+            .map(|response| {
+                response
+                    .errors
+                    .into_iter()
+                    .filter_map(|error| match (error.field, error.reason) {
+                        (Some(field), Some(reason)) => Some(format!("{field}: {reason}")),
+                        (None, reason) => reason,
+                        (Some(_), None) => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .filter(|detail| !detail.is_empty())
+            .or_else(|| body_snippet(&body));
+        let detail = detail.map_or_else(String::new, |detail| format!(": {detail}"));
+        return Err(anyhow!("failed to {action}: HTTP {status}{detail}"));
+    }
+
+    serde_json::from_str::<T>(&body)
+        .map_err(|error| anyhow!("failed to parse Linode response for {action}: {error}"))
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[async_trait]
+#[typetag::deserialize(name = "linode")]
+impl Provider for Linode {
+    fn validate_config(&self) -> Result<()> {
+        if self.domain.trim().is_empty() {
+            return Err(anyhow!("Linode domain must not be empty"));
+        }
+        if self.api_token.expose_secret().trim().is_empty() {
+            return Err(anyhow!("Linode api_token must not be empty"));
+        }
+        if self.domains.is_empty() {
+            return Err(anyhow!("no domains configured for Linode provider"));
+        }
+        for domain in &self.domains {
+            if domain.name != self.domain && !domain.name.ends_with(&format!(".{}", self.domain)) {
+                return Err(anyhow!(
+                    "Linode domain ({}) is not within {}",
+                    domain.name,
+                    self.domain
+                ));
+            }
+            match round_ttl(domain.ttl) {
+                None => {
+                    return Err(anyhow!(
+                        "Linode domain ({}) TTL must be at most {}",
+                        domain.name,
+                        ALLOWED_TTLS[ALLOWED_TTLS.len() - 1]
+                    ));
+                }
+                Some(rounded) if rounded != domain.ttl => warn!(
+                    "Linode domain ({}) TTL {} is not an allowed value, {rounded} will be used",
+                    domain.name, domain.ttl
+                ),
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    async fn update(&self, update: IpUpdate, request: HttpClient) -> Result<bool> {
+        let domain_id = self.fetch_domain_id(&request).await?;
+        let records = self
+            .fetch_all::<RecordResult>(
+                &request,
+                &format!("{}/domains/{domain_id}/records", self.api_url),
+                &format!("list Linode DNS records for {}", self.domain),
+            )
+            .await?;
+
+        for domain in &self.domains {
+            let name = self.record_name(domain);
+            for (version, addr) in update.iter() {
+                let record_type = match version {
+                    IpVersion::V4 => "A",
+                    IpVersion::V6 => "AAAA",
+                };
+                if let Some(record) = records.iter().find(|record| {
+                    record.kind == record_type && record.name.eq_ignore_ascii_case(name)
+                }) {
+                    self.update_dns_record(&request, domain_id, record.id, domain, &addr)
+                        .await?;
+                } else {
+                    self.create_dns_record(&request, domain_id, record_type, domain, &addr)
+                        .await?;
+                }
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use smallvec::smallvec;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{bearer_token, body_json, method, path, query_param},
+    };
+
+    use super::*;
+
+    const UPDATE_BOTH: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+    const UPDATE_V4: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: None,
+    };
+
+    fn provider(api_url: String) -> Linode {
+        Linode {
+            domain: "example.com".into(),
+            api_token: "token".into(),
+            domains: smallvec![
+                Domain {
+                    name: "example.com".into(),
+                    ttl: 300,
+                },
+                Domain {
+                    name: "www.example.com".into(),
+                    ttl: 600,
+                },
+            ],
+            api_url,
+        }
+    }
+
+    async fn mount_domains(mock: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path("/domains"))
+            .and(query_param("page", "1"))
+            .and(bearer_token("token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [{"id": 1, "domain": "other.com"}],
+                "page": 1,
+                "pages": 2,
+                "results": 2
+            })))
+            .expect(1)
+            .mount(mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/domains"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [{"id": 42, "domain": "Example.com"}],
+                "page": 2,
+                "pages": 2,
+                "results": 2
+            })))
+            .expect(1)
+            .mount(mock)
+            .await;
+    }
+
+    #[test]
+    fn test_linode_deserializes_from_config() {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+[[providers]]
+type = "linode"
+domain = "example.com"
+api_token = "token"
+
+[[providers.domains]]
+name = "www.example.com"
+ttl = 3600
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.providers.len(), 1);
+        config.providers[0].validate_config().unwrap();
+    }
+
+    #[test]
+    fn test_linode_rounds_ttl() {
+        assert_eq!(round_ttl(0), Some(0));
+        assert_eq!(round_ttl(1), Some(30));
+        assert_eq!(round_ttl(30), Some(30));
+        assert_eq!(round_ttl(31), Some(120));
+        assert_eq!(round_ttl(121), Some(300));
+        assert_eq!(round_ttl(300), Some(300));
+        assert_eq!(round_ttl(600), Some(3600));
+        assert_eq!(round_ttl(2_419_200), Some(2_419_200));
+        assert_eq!(round_ttl(2_419_201), None);
+    }
+
+    #[tokio::test]
+    async fn test_linode_updates_and_creates_records() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        mount_domains(&mock).await;
+        Mock::given(method("GET"))
+            .and(path("/domains/42/records"))
+            .and(query_param("page", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [
+                    {"id": 7, "type": "A", "name": "", "target": "192.0.2.1", "ttl_sec": 300},
+                    {"id": 8, "type": "MX", "name": "", "target": "mail.example.com", "ttl_sec": 300}
+                ],
+                "page": 1,
+                "pages": 2,
+                "results": 3
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/domains/42/records"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [
+                    {"id": 9, "type": "AAAA", "name": "www", "target": "2001:db8::1", "ttl_sec": 3600}
+                ],
+                "page": 2,
+                "pages": 2,
+                "results": 3
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        for (record_id, target, ttl) in [(7, "127.0.0.1", 300), (9, "::1", 3600)] {
+            Mock::given(method("PUT"))
+                .and(path(format!("/domains/42/records/{record_id}")))
+                .and(bearer_token("token"))
+                .and(body_json(json!({"target": target, "ttl_sec": ttl})))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": record_id})))
+                .expect(1)
+                .mount(&mock)
+                .await;
+        }
+        for (record_type, name, target, ttl) in
+            [("AAAA", "", "::1", 300), ("A", "www", "127.0.0.1", 3600)]
+        {
+            Mock::given(method("POST"))
+                .and(path("/domains/42/records"))
+                .and(body_json(json!({
+                    "type": record_type,
+                    "name": name,
+                    "target": target,
+                    "ttl_sec": ttl
+                })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": 10})))
+                .expect(1)
+                .mount(&mock)
+                .await;
+        }
+
+        let result = provider.update(UPDATE_BOTH, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_linode_missing_domain() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/domains"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [],
+                "page": 1,
+                "pages": 1,
+                "results": 0
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Linode domain (example.com) was not found"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_linode_surfaces_errors() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/domains"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+                "errors": [{"reason": "Invalid Token"}]
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to list Linode domains: HTTP 401 Unauthorized: Invalid Token"
+        );
+    }
+
+    #[test]
+    fn test_linode_validates_config() {
+        let mut provider = provider(default_api_url());
+        provider.validate_config().unwrap();
+
+        provider.domains[1].ttl = 3_000_000;
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "Linode domain (www.example.com) TTL must be at most 2419200"
+        );
+
+        provider.domains[1].name = "www.example.org".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "Linode domain (www.example.org) is not within example.com"
+        );
+    }
+}
@@ -0,0 +1,461 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use compact_str::CompactString;
+use reqwest::Response;
+use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use smallvec::{SmallVec, smallvec};
+
+use crate::config::IpVersions;
+use crate::ip::{IpUpdate, IpVersion};
+use crate::providers::Provider;
+
+/// Namecheap dynamic DNS update provider
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Namecheap {
+    domains: SmallVec<[Domain; 2]>,
+    #[serde(default = "default_api_url")]
+    api_url: String,
+}
+
+fn default_api_url() -> String {
+    "https://dynamicdns.park-your-domain.com".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct Domain {
+    name: CompactString,
+    password: SecretString,
+    #[serde(default = "default_hosts")]
+    hosts: SmallVec<[CompactString; 2]>,
+}
+
+fn default_hosts() -> SmallVec<[CompactString; 2]> {
+    smallvec!["@".into()]
+}
+
+impl Domain {
+    fn fqdn(&self, host: &str) -> String {
+        if host == "@" {
+            self.name.to_string()
+        } else {
+            format!("{host}.{}", self.name)
+        }
+    }
+}
+
+/// Parsed `interface-response` document returned by the update endpoint
+#[derive(Debug, Default, PartialEq, Eq)]
+struct UpdateResponse {
+    err_count: u32,
+    errors: Vec<String>,
+    responses: Vec<String>,
+    done: bool,
+}
+
+impl Namecheap {
+    fn update_url(&self) -> String {
+        format!("{}/update", self.api_url.trim_end_matches('/'))
+    }
+
+    async fn update_host(
+        &self,
+        request: &HttpClient,
+        domain: &Domain,
+        host: &str,
+        ip: String,
+    ) -> Result<()> {
+        let response = request
+            .get(self.update_url())
+            .query(&[
+                ("host", host),
+                ("domain", domain.name.as_str()),
+                ("password", domain.password.expose_secret()),
+                ("ip", ip.as_str()),
+            ])
+            .send()
+            .await?;
+
+        parse_namecheap_response(
+            response,
+            &format!("update Namecheap host ({})", domain.fqdn(host)),
+        )
+        .await
+    }
+}
+
+async fn parse_namecheap_response(response: Response, action: &str) -> Result<()> {
+    let status = response.status();
+    let body = response.text().await?;
+
+    if !status.is_success() {
+        let detail = body_snippet(&body).map_or_else(String::new, |body| format!(": {body}"));
+        return Err(anyhow!("failed to {action}: HTTP {status}{detail}"));
+    }
+
+    let parsed = parse_update_xml(&body).map_err(|error| {
+        let detail = body_snippet(&body).map_or_else(String::new, |body| format!(": {body}"));
+        anyhow!("failed to {action}: invalid response ({error}){detail}")
+    })?;
+
+    if parsed.err_count > 0 || !parsed.errors.is_empty() {
+        let mut details = parsed.errors;
+        details.extend(parsed.responses);
+        details.dedup();
+        let detail = if details.is_empty() {
+            format!("{} error(s) reported", parsed.err_count)
+        } else {
+            details.join("; ")
+        };
+        return Err(anyhow!("failed to {action}: {detail}"));
+    }
+    if !parsed.done {
+        return Err(anyhow!("failed to {action}: response was not marked done"));
+    }
+
+    Ok(())
+}
+
+fn parse_update_xml(body: &str) -> Result<UpdateResponse> {
+    // The endpoint declares utf-16 but sends UTF-8, which roxmltree ignores
+    let document = roxmltree::Document::parse(body.trim_start_matches('\u{feff}'))?;
+    let root = document.root_element();
+    if !root.has_tag_name("interface-response") {
+        return Err(anyhow!(
+            "unexpected root element <{}>",
+            root.tag_name().name()
+        ));
+    }
+
+    let mut parsed = UpdateResponse::default();
+    for node in root.children().filter(roxmltree::Node::is_element) {
+        match node.tag_name().name() {
+            "ErrCount" => {
+                let text = node.text().unwrap_or_default().trim();
+                parsed.err_count = text
+                    .parse()
+                    .map_err(|_| anyhow!("invalid ErrCount value ({text})"))?;
+            }
+            // Individual errors are reported as <Err1>, <Err2>, ...
+            "errors" => parsed.errors.extend(
+                node.children()
+                    .filter(roxmltree::Node::is_element)
+                    .filter_map(|error| error.text())
+                    .map(str::trim)
+                    .filter(|error| !error.is_empty())
+                    .map(str::to_string),
+            ),
+            "responses" => parsed.responses.extend(
+                node.descendants()
+                    .filter(|response| response.has_tag_name("ResponseString"))
+                    .filter_map(|response| response.text())
+                    .map(str::trim)
+                    .filter(|response| !response.is_empty())
+                    .map(str::to_string),
+            ),
+            "Done" => {
+                parsed.done = node
+                    .text()
+                    .is_some_and(|done| done.trim().eq_ignore_ascii_case("true"));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(parsed)
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[async_trait]
+#[typetag::deserialize(name = "namecheap")]
+impl Provider for Namecheap {
+    fn validate_config(&self) -> Result<()> {
+        if self.domains.is_empty() {
+            return Err(anyhow!("no domains configured for Namecheap provider"));
+        }
+        for domain in &self.domains {
+            if domain.name.trim().is_empty() {
+                return Err(anyhow!("Namecheap domain names must not be empty"));
+            }
+            if domain.password.expose_secret().trim().is_empty() {
+                return Err(anyhow!(
+                    "Namecheap domain ({}) password must not be empty",
+                    domain.name
+                ));
+            }
+            if domain.hosts.is_empty() {
+                return Err(anyhow!(
+                    "Namecheap domain ({}) hosts must not be empty",
+                    domain.name
+                ));
+            }
+            if domain.hosts.iter().any(|host| host.trim().is_empty()) {
+                return Err(anyhow!(
+                    "Namecheap domain ({}) hosts must not contain empty names, use @ for the root",
+                    domain.name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_versions(&self, versions: IpVersions) -> Result<()> {
+        if versions.contains(IpVersion::V6) {
+            return Err(anyhow!(
+                "Namecheap dynamic DNS only supports IPv4, remove v6 from versions"
+            ));
+        }
+        Ok(())
+    }
+
+    async fn update(&self, update: IpUpdate, request: HttpClient) -> Result<bool> {
+        let Some(ip) = update.v4 else {
+            return Err(anyhow!("no IPv4 address supplied for Namecheap update"));
+        };
+
+        for domain in &self.domains {
+            for host in &domain.hosts {
+                self.update_host(&request, domain, host, ip.to_string())
+                    .await?;
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path, query_param},
+    };
+
+    use super::*;
+
+    const UPDATE_V4: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: None,
+    };
+    const UPDATE_V6: IpUpdate = IpUpdate {
+        v4: None,
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+
+    const SUCCESS_BODY: &str = r#"<?xml version="1.0" encoding="utf-16"?>
+<interface-response>
+  <Command>SETDNSHOST</Command>
+  <Language>eng</Language>
+  <IP>127.0.0.1</IP>
+  <ErrCount>0</ErrCount>
+  <errors />
+  <ResponseCount>0</ResponseCount>
+  <responses />
+  <Done>true</Done>
+  <debug><![CDATA[]]></debug>
+</interface-response>"#;
+
+    const BAD_PASSWORD_BODY: &str = r#"<?xml version="1.0" encoding="utf-16"?>
+<interface-response>
+  <Command>SETDNSHOST</Command>
+  <Language>eng</Language>
+  <ErrCount>1</ErrCount>
+  <errors>
+    <Err1>Passwords do not match</Err1>
+  </errors>
+  <ResponseCount>1</ResponseCount>
+  <responses>
+    <response>
+      <ResponseNumber>304156</ResponseNumber>
+      <ResponseString>Validation error; invalid ; password</ResponseString>
+    </response>
+  </responses>
+  <Done>true</Done>
+  <debug><![CDATA[]]></debug>
+</interface-response>"#;
+
+    fn provider(api_url: String) -> Namecheap {
+        Namecheap {
+            domains: smallvec![Domain {
+                name: "example.com".into(),
+                password: "password".into(),
+                hosts: smallvec!["@".into(), "*".into(), "www".into()],
+            }],
+            api_url,
+        }
+    }
+
+    #[test]
+    fn test_namecheap_deserializes_from_config() {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+[[providers]]
+type = "namecheap"
+
+[[providers.domains]]
+name = "example.com"
+password = "password"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.providers.len(), 1);
+        config.providers[0].validate_config().unwrap();
+    }
+
+    #[test]
+    fn test_namecheap_rejects_ipv6_versions() {
+        let error = toml::from_str::<crate::config::Config>(
+            r#"
+versions = ["v4", "v6"]
+
+[[providers]]
+type = "namecheap"
+
+[[providers.domains]]
+name = "example.com"
+password = "password"
+"#,
+        )
+        .unwrap_err();
+
+        assert!(
+            error
+                .to_string()
+                .contains("Namecheap dynamic DNS only supports IPv4, remove v6 from versions")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_namecheap_updates_each_host() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        for host in ["@", "*", "www"] {
+            Mock::given(method("GET"))
+                .and(path("/update"))
+                .and(query_param("host", host))
+                .and(query_param("domain", "example.com"))
+                .and(query_param("password", "password"))
+                .and(query_param("ip", "127.0.0.1"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(SUCCESS_BODY))
+                .expect(1)
+                .mount(&mock)
+                .await;
+        }
+
+        let result = provider.update(UPDATE_V4, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_namecheap_reports_xml_errors() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/update"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(BAD_PASSWORD_BODY))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to update Namecheap host (example.com): Passwords do not match; Validation error; invalid ; password"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_namecheap_rejects_invalid_xml() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/update"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<html>maintenance</html>"))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to update Namecheap host (example.com): invalid response (unexpected root element <html>): <html>maintenance</html>"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_namecheap_requires_ipv4_update() {
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(default_api_url());
+
+        let error = provider.update(UPDATE_V6, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "no IPv4 address supplied for Namecheap update"
+        );
+    }
+
+    #[test]
+    fn test_namecheap_parses_response_fields() {
+        let parsed = parse_update_xml(BAD_PASSWORD_BODY).unwrap();
+        assert_eq!(
+            parsed,
+            UpdateResponse {
+                err_count: 1,
+                errors: vec!["Passwords do not match".to_string()],
+                responses: vec!["Validation error; invalid ; password".to_string()],
+                done: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_namecheap_validates_config() {
+        let mut provider = provider(default_api_url());
+        provider.validate_config().unwrap();
+
+        provider.domains[0].hosts.push(" ".into());
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "Namecheap domain (example.com) hosts must not contain empty names, use @ for the root"
+        );
+
+        provider.domains[0].password = "".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "Namecheap domain (example.com) password must not be empty"
+        );
+    }
+}
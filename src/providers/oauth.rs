@@ -0,0 +1,102 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow};
+use reqwest::Response;
+use secrecy::SecretString;
+use serde::Deserialize;
+
+// Refresh cached tokens this long before they expire
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_mins(1);
+
+/// An OAuth access token cache shared by all clones of a provider
+#[derive(Debug, Clone, Default)]
+pub(super) struct TokenCache(Arc<Mutex<Option<AccessToken>>>);
+
+#[derive(Debug, Clone)]
+struct AccessToken {
+    token: SecretString,
+    expires_at: Instant,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: SecretString,
+    expires_in: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenError {
+    error: String,
+    error_description: Option<String>,
+}
+
+impl TokenCache {
+    /// The cached token, unless it is about to expire.
+    pub(super) fn get(&self) -> Option<SecretString> {
+        let cached = self.0.lock().ok()?;
+        cached
+            .as_ref()
+            .filter(|token| Instant::now() + TOKEN_EXPIRY_MARGIN < token.expires_at)
+            .map(|token| token.token.clone())
+    }
+
+    pub(super) fn clear(&self) {
+        if let Ok(mut cached) = self.0.lock() {
+            *cached = None;
+        }
+    }
+
+    /// Caches the token from a token endpoint response and returns it.
+    pub(super) async fn store(&self, response: Response, issuer: &str) -> Result<SecretString> {
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            let detail = serde_json::from_str::<TokenError>(&body)
+                .ok()
+                .map(|error| match error.error_description {
+                    Some(description) => format!("{}: {description}", error.error),
+                    None => error.error,
+                })
+                .or_else(|| body_snippet(&body))
+                .map_or_else(String::new, |detail| format!(": {detail}"));
+            return Err(anyhow!(
+                "failed to fetch {issuer} access token: HTTP {status}{detail}"
+            ));
+        }
+
+        let response = serde_json::from_str::<TokenResponse>(&body)
+            .with_context(|| format!("failed to parse {issuer} token response"))?;
+        let token = AccessToken {
+            token: response.access_token,
+            expires_at: Instant::now() + Duration::from_secs(response.expires_in),
+        };
+        if let Ok(mut cached) = self.0.lock() {
+            *cached = Some(token.clone());
+        }
+        Ok(token.token)
+    }
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
@@ -0,0 +1,742 @@
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, anyhow};
+use async_trait::async_trait;
+use compact_str::CompactString;
+use reqwest::{Method, Response};
+use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use sha1::{Digest, Sha1};
+use smallvec::SmallVec;
+use tracing::info;
+
+use crate::ip::{IpUpdate, IpVersion};
+use crate::providers::Provider;
+
+/// OVH DNS API update provider
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Ovh {
+    #[serde(default)]
+    endpoint: Endpoint,
+    application_key: CompactString,
+    application_secret: SecretString,
+    consumer_key: SecretString,
+    zone: CompactString,
+    domains: SmallVec<[Domain; 2]>,
+    api_url: Option<String>,
+    /// Offset between the OVH server clock and the local clock, in seconds
+    #[serde(skip)]
+    time_delta: Arc<Mutex<Option<i64>>>,
+    /// Set once a record is edited, until the zone refresh succeeds
+    #[serde(skip)]
+    refresh_pending: Arc<AtomicBool>,
+}
+
+/// OVH API regions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+enum Endpoint {
+    #[default]
+    #[serde(rename = "ovh-eu")]
+    Eu,
+    #[serde(rename = "ovh-ca")]
+    Ca,
+    #[serde(rename = "ovh-us")]
+    Us,
+}
+
+impl Endpoint {
+    fn url(self) -> &'static str {
+        match self {
+            Self::Eu => "https://eu.api.ovh.com/1.0",
+            Self::Ca => "https://ca.api.ovh.com/1.0",
+            Self::Us => "https://api.us.ovhcloud.com/1.0",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+struct Domain {
+    subdomain: CompactString,
+    #[serde(default = "default_ttl")]
+    ttl: u32,
+}
+
+fn default_ttl() -> u32 {
+    3600
+}
+
+#[derive(Debug, Deserialize)]
+struct OvhError {
+    message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ZoneRecord {
+    target: String,
+    ttl: Option<u32>,
+}
+
+impl Ovh {
+    fn api_url(&self) -> &str {
+        self.api_url
+            .as_deref()
+            .unwrap_or(self.endpoint.url())
+            .trim_end_matches('/')
+    }
+
+    fn fqdn(&self, domain: &Domain) -> String {
+        if domain.subdomain.is_empty() {
+            self.zone.to_string()
+        } else {
+            format!("{}.{}", domain.subdomain, self.zone)
+        }
+    }
+
+    /// Server time, using the cached clock offset once it has been fetched.
+    async fn timestamp(&self, request: &HttpClient) -> Result<i64> {
+        let now = i64::try_from(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .context("system clock is before the UNIX epoch")?
+                .as_secs(),
+        )?;
+        if let Some(delta) = self.time_delta.lock().ok().and_then(|delta| *delta) {
+            return Ok(now + delta);
+        }
+
+        let response = request
+            .get(format!("{}/auth/time", self.api_url()))
+            .send()
+            .await?;
+        let server_time = parse_ovh_response::<i64>(response, "fetch OVH server time").await?;
+        let delta = server_time - now;
+        if let Ok(mut cached) = self.time_delta.lock() {
+            *cached = Some(delta);
+        }
+        Ok(now + delta)
+    }
+
+    async fn send_signed<T>(
+        &self,
+        request: &HttpClient,
+        method: Method,
+        path: &str,
+        body: Option<&Value>,
+        action: &str,
+    ) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let url = format!("{}{path}", self.api_url());
+        let body = body.map(Value::to_string).unwrap_or_default();
+        let timestamp = self.timestamp(request).await?;
+        let signature = sign_request(
+            self.application_secret.expose_secret(),
+            self.consumer_key.expose_secret(),
+            method.as_str(),
+            &url,
+            &body,
+            timestamp,
+        );
+
+        let mut builder = request
+            .request(method, url)
+            .header("X-Ovh-Application", self.application_key.as_str())
+            .header("X-Ovh-Consumer", self.consumer_key.expose_secret())
+            .header("X-Ovh-Timestamp", timestamp.to_string())
+            .header("X-Ovh-Signature", signature);
+        if !body.is_empty() {
+            builder = builder
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body);
+        }
+        let response = builder.send().await?;
+        parse_ovh_response(response, action).await
+    }
+
+    async fn find_record(
+        &self,
+        request: &HttpClient,
+        record_type: &str,
+        domain: &Domain,
+    ) -> Result<Option<(u64, ZoneRecord)>> {
+        let ids = self
+            .send_signed::<Vec<u64>>(
+                request,
+                Method::GET,
+                &format!(
+                    "/domain/zone/{}/record?fieldType={record_type}&subDomain={}",
+                    self.zone, domain.subdomain
+                ),
+                None,
+                &format!("list OVH ({}) {record_type} records", self.fqdn(domain)),
+            )
+            .await?;
+        let Some(id) = ids.first() else {
+            return Ok(None);
+        };
+
+        let record = self
+            .send_signed::<ZoneRecord>(
+                request,
+                Method::GET,
+                &format!("/domain/zone/{}/record/{id}", self.zone),
+                None,
+                &format!("get OVH ({}) {record_type} record", self.fqdn(domain)),
+            )
+            .await?;
+        Ok(Some((*id, record)))
+    }
+
+    /// Point the record at `target`, returning whether anything changed.
+    async fn upsert_record(
+        &self,
+        request: &HttpClient,
+        record_type: &str,
+        domain: &Domain,
+        target: String,
+    ) -> Result<bool> {
+        match self.find_record(request, record_type, domain).await? {
+            Some((_, record)) if record.target == target && record.ttl == Some(domain.ttl) => {
+                Ok(false)
+            }
+            Some((id, _)) => {
+                self.refresh_pending.store(true, Ordering::Relaxed);
+                self.send_signed::<Value>(
+                    request,
+                    Method::PUT,
+                    &format!("/domain/zone/{}/record/{id}", self.zone),
+                    Some(&json!({ "target": target, "ttl": domain.ttl })),
+                    &format!("update OVH ({}) {record_type} record", self.fqdn(domain)),
+                )
+                .await?;
+                Ok(true)
+            }
+            None => {
+                self.refresh_pending.store(true, Ordering::Relaxed);
+                self.send_signed::<Value>(
+                    request,
+                    Method::POST,
+                    &format!("/domain/zone/{}/record", self.zone),
+                    Some(&json!({
+                        "fieldType": record_type,
+                        "subDomain": domain.subdomain,
+                        "target": target,
+                        "ttl": domain.ttl,
+                    })),
+                    &format!("create OVH ({}) {record_type} record", self.fqdn(domain)),
+                )
+                .await?;
+                Ok(true)
+            }
+        }
+    }
+}
+
+/// `$1$` followed by the hex SHA1 of the `+` separated request parts.
+fn sign_request(
+    application_secret: &str,
+    consumer_key: &str,
+    method: &str,
+    url: &str,
+    body: &str,
+    timestamp: i64,
+) -> String {
+    let digest = Sha1::digest(
+        format!("{application_secret}+{consumer_key}+{method}+{url}+{body}+{timestamp}").as_bytes(),
+    );
+    let mut signature = String::from("$1$");
+    for byte in digest {
+        let _ = write!(signature, "{byte:02x}");
+    }
+    signature
+}
+
+async fn parse_ovh_response<T>(response: Response, action: &str) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let status = response.status();
+    let body = response.text().await?;
+
+    if !status.is_success() {
+        let detail = serde_json::from_str::<OvhError>(&body)
+            .ok()
+            .and_then(|error| error.message)
+            .filter(|message| !message.is_empty())
+            .or_else(|| body_snippet(&body));
+        let detail = detail.map_or_else(String::new, |detail| format!(": {detail}"));
+        return Err(anyhow!("failed to {action}: HTTP {status}{detail}"));
+    }
+
+    serde_json::from_str::<T>(&body)
+        .map_err(|error| anyhow!("failed to parse OVH response for {action}: {error}"))
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[async_trait]
+#[typetag::deserialize(name = "ovh")]
+impl Provider for Ovh {
+    fn validate_config(&self) -> Result<()> {
+        if self.application_key.trim().is_empty() {
+            return Err(anyhow!("OVH application_key must not be empty"));
+        }
+        if self.application_secret.expose_secret().trim().is_empty() {
+            return Err(anyhow!("OVH application_secret must not be empty"));
+        }
+        if self.consumer_key.expose_secret().trim().is_empty() {
+            return Err(anyhow!("OVH consumer_key must not be empty"));
+        }
+        if self.zone.trim().is_empty() {
+            return Err(anyhow!("OVH zone must not be empty"));
+        }
+        if self.domains.is_empty() {
+            return Err(anyhow!("no domains configured for OVH provider"));
+        }
+        for domain in &self.domains {
+            // The subdomain is sent unencoded in the signed query string
+            if !domain
+                .subdomain
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '*' | '.' | '_' | '-'))
+            {
+                return Err(anyhow!(
+                    "OVH subdomain ({}) contains invalid characters",
+                    domain.subdomain
+                ));
+            }
+            if domain.ttl < 60 {
+                return Err(anyhow!(
+                    "OVH domain ({}) TTL must be at least 60",
+                    self.fqdn(domain)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn update(&self, update: IpUpdate, request: HttpClient) -> Result<bool> {
+        let mut changed = false;
+        for domain in &self.domains {
+            for (version, addr) in update.iter() {
+                let record_type = match version {
+                    IpVersion::V4 => "A",
+                    IpVersion::V6 => "AAAA",
+                };
+                changed |= self
+                    .upsert_record(&request, record_type, domain, addr.to_string())
+                    .await?;
+            }
+        }
+
+        // Record changes are only published once the zone is refreshed, so a
+        // refresh missed by an earlier failed update is retried here
+        if self.refresh_pending.load(Ordering::Relaxed) {
+            self.send_signed::<Value>(
+                &request,
+                Method::POST,
+                &format!("/domain/zone/{}/refresh", self.zone),
+                None,
+                &format!("refresh OVH zone ({})", self.zone),
+            )
+            .await?;
+            self.refresh_pending.store(false, Ordering::Relaxed);
+            info!("refreshed OVH zone {}", self.zone);
+        }
+
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use smallvec::smallvec;
+    use wiremock::{
+        Mock, MockServer, Request, ResponseTemplate,
+        matchers::{body_json, header, header_exists, method, path, query_param},
+    };
+
+    use super::*;
+
+    const UPDATE_BOTH: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+    const UPDATE_V4: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: None,
+    };
+
+    fn provider(api_url: String) -> Ovh {
+        Ovh {
+            endpoint: Endpoint::Eu,
+            application_key: "app-key".into(),
+            application_secret: "app-secret".into(),
+            consumer_key: "consumer-key".into(),
+            zone: "example.com".into(),
+            domains: smallvec![
+                Domain {
+                    subdomain: "".into(),
+                    ttl: 3600,
+                },
+                Domain {
+                    subdomain: "www".into(),
+                    ttl: 60,
+                },
+            ],
+            api_url: Some(api_url),
+            time_delta: Arc::default(),
+            refresh_pending: Arc::default(),
+        }
+    }
+
+    async fn mount_time(mock: &MockServer) {
+        let server_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 120;
+        Mock::given(method("GET"))
+            .and(path("/auth/time"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(server_time))
+            .expect(1)
+            .mount(mock)
+            .await;
+    }
+
+    #[test]
+    fn test_ovh_signs_request() {
+        let signature = sign_request(
+            "app-secret",
+            "consumer-key",
+            "GET",
+            "https://eu.api.ovh.com/1.0/domain/zone/example.com/record",
+            "",
+            1_700_000_000,
+        );
+        assert_eq!(signature, "$1$bd4d086e84f4df48be8d6a5553cee9d638097428");
+    }
+
+    #[test]
+    fn test_ovh_deserializes_from_config() {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+[[providers]]
+type = "ovh"
+endpoint = "ovh-ca"
+application_key = "app-key"
+application_secret = "app-secret"
+consumer_key = "consumer-key"
+zone = "example.com"
+
+[[providers.domains]]
+subdomain = "www"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.providers.len(), 1);
+        config.providers[0].validate_config().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ovh_updates_creates_and_refreshes() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        mount_time(&mock).await;
+        for (record_type, subdomain, ids) in [
+            ("A", "", json!([11])),
+            ("AAAA", "", json!([12])),
+            ("A", "www", json!([])),
+            ("AAAA", "www", json!([])),
+        ] {
+            Mock::given(method("GET"))
+                .and(path("/domain/zone/example.com/record"))
+                .and(query_param("fieldType", record_type))
+                .and(query_param("subDomain", subdomain))
+                .and(header("X-Ovh-Application", "app-key"))
+                .and(header("X-Ovh-Consumer", "consumer-key"))
+                .and(header_exists("X-Ovh-Timestamp"))
+                .and(header_exists("X-Ovh-Signature"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(ids))
+                .expect(1)
+                .mount(&mock)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/domain/zone/example.com/record/11"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": 11, "fieldType": "A", "subDomain": "", "target": "192.0.2.1", "ttl": 3600, "zone": "example.com"
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/domain/zone/example.com/record/12"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": 12, "fieldType": "AAAA", "subDomain": "", "target": "::1", "ttl": 3600, "zone": "example.com"
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/domain/zone/example.com/record/11"))
+            .and(body_json(json!({"target": "127.0.0.1", "ttl": 3600})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(Value::Null))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/domain/zone/example.com/record/12"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(Value::Null))
+            .expect(0)
+            .mount(&mock)
+            .await;
+        for (record_type, target) in [("A", "127.0.0.1"), ("AAAA", "::1")] {
+            Mock::given(method("POST"))
+                .and(path("/domain/zone/example.com/record"))
+                .and(body_json(json!({
+                    "fieldType": record_type,
+                    "subDomain": "www",
+                    "target": target,
+                    "ttl": 60
+                })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": 13})))
+                .expect(1)
+                .mount(&mock)
+                .await;
+        }
+        Mock::given(method("POST"))
+            .and(path("/domain/zone/example.com/refresh"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(Value::Null))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_BOTH, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_ovh_signs_with_server_time() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let mut provider = provider(mock.uri());
+        provider.domains.truncate(1);
+
+        mount_time(&mock).await;
+        let uri = mock.uri();
+        Mock::given(method("GET"))
+            .and(path("/domain/zone/example.com/record"))
+            .and(move |request: &Request| {
+                let header = |name| request.headers.get(name).unwrap().to_str().unwrap();
+                let timestamp = header("X-Ovh-Timestamp");
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs();
+                let expected = sign_request(
+                    "app-secret",
+                    "consumer-key",
+                    "GET",
+                    &format!("{uri}/domain/zone/example.com/record?fieldType=A&subDomain="),
+                    "",
+                    timestamp.parse().unwrap(),
+                );
+                timestamp.parse::<u64>().unwrap().abs_diff(now + 120) <= 2
+                    && header("X-Ovh-Signature") == expected
+            })
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(Value::Null))
+            .mount(&mock)
+            .await;
+
+        provider.update(UPDATE_V4, http).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ovh_unchanged_skips_refresh() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let mut provider = provider(mock.uri());
+        provider.domains.truncate(1);
+
+        mount_time(&mock).await;
+        Mock::given(method("GET"))
+            .and(path("/domain/zone/example.com/record"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([11])))
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/domain/zone/example.com/record/11"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": 11, "fieldType": "A", "subDomain": "", "target": "127.0.0.1", "ttl": 3600
+            })))
+            .mount(&mock)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(Value::Null))
+            .expect(0)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_V4, http).await.unwrap();
+        assert!(!result);
+    }
+
+    #[tokio::test]
+    async fn test_ovh_retries_failed_refresh() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let mut provider = provider(mock.uri());
+        provider.domains.truncate(1);
+
+        mount_time(&mock).await;
+        Mock::given(method("GET"))
+            .and(path("/domain/zone/example.com/record"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([11])))
+            .mount(&mock)
+            .await;
+        // OVH shows the edited record before the zone is refreshed
+        Mock::given(method("GET"))
+            .and(path("/domain/zone/example.com/record/11"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": 11, "fieldType": "A", "subDomain": "", "target": "192.0.2.1", "ttl": 3600
+            })))
+            .up_to_n_times(1)
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/domain/zone/example.com/record/11"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": 11, "fieldType": "A", "subDomain": "", "target": "127.0.0.1", "ttl": 3600
+            })))
+            .mount(&mock)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/domain/zone/example.com/record/11"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(Value::Null))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/domain/zone/example.com/refresh"))
+            .respond_with(ResponseTemplate::new(500).set_body_json(json!({
+                "message": "Internal server error"
+            })))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/domain/zone/example.com/refresh"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(Value::Null))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http.clone()).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to refresh OVH zone (example.com): HTTP 500 Internal Server Error: Internal server error"
+        );
+
+        // Records already match, but the zone still has to be published
+        assert!(!provider.update(UPDATE_V4, http.clone()).await.unwrap());
+        assert!(!provider.update(UPDATE_V4, http).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_ovh_surfaces_errors() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        mount_time(&mock).await;
+        Mock::given(method("GET"))
+            .and(path("/domain/zone/example.com/record"))
+            .respond_with(ResponseTemplate::new(403).set_body_json(json!({
+                "errorCode": "INVALID_CREDENTIAL",
+                "httpCode": "403 Forbidden",
+                "message": "This credential is not valid"
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to list OVH (example.com) A records: HTTP 403 Forbidden: This credential is not valid"
+        );
+    }
+
+    #[test]
+    fn test_ovh_validates_config() {
+        let mut provider = provider(Endpoint::Us.url().to_string());
+        provider.validate_config().unwrap();
+
+        for subdomain in ["www&x=1", "a b", "caf\u{e9}", "%20", "a+b", "a#b"] {
+            provider.domains[1].subdomain = subdomain.into();
+            assert_eq!(
+                provider.validate_config().unwrap_err().to_string(),
+                format!("OVH subdomain ({subdomain}) contains invalid characters")
+            );
+        }
+        for subdomain in ["*", "_acme-challenge.home", "www"] {
+            provider.domains[1].subdomain = subdomain.into();
+            provider.validate_config().unwrap();
+        }
+
+        provider.domains[1].ttl = 30;
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "OVH domain (www.example.com) TTL must be at least 60"
+        );
+
+        provider.consumer_key = "".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "OVH consumer_key must not be empty"
+        );
+    }
+}
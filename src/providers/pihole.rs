@@ -0,0 +1,465 @@
+use std::net::IpAddr;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use compact_str::CompactString;
+use reqwest::Response;
+use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use serde_json::json;
+use smallvec::SmallVec;
+use tracing::warn;
+use url::Url;
+
+use crate::ip::IpUpdate;
+use crate::providers::Provider;
+
+/// Pi-hole (v6+) local DNS records update provider
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct PiHole {
+    api_url: String,
+    password: SecretString,
+    domains: SmallVec<[Domain; 2]>,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct Domain {
+    name: CompactString,
+}
+
+#[derive(Debug, Deserialize)]
+struct AuthResponse {
+    session: Session,
+}
+
+#[derive(Debug, Deserialize)]
+struct Session {
+    valid: bool,
+    sid: Option<SecretString>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HostsResponse {
+    config: HostsConfig,
+}
+
+#[derive(Debug, Deserialize)]
+struct HostsConfig {
+    dns: HostsDns,
+}
+
+#[derive(Debug, Deserialize)]
+struct HostsDns {
+    hosts: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PiHoleError {
+    error: PiHoleErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct PiHoleErrorDetail {
+    key: Option<String>,
+    message: Option<String>,
+    hint: Option<String>,
+}
+
+impl PiHoleError {
+    fn summary(self) -> Option<String> {
+        let PiHoleErrorDetail { key, message, hint } = self.error;
+        let summary = match (message.or(key), hint) {
+            (Some(message), Some(hint)) => format!("{message} ({hint})"),
+            (Some(detail), None) | (None, Some(detail)) => detail,
+            (None, None) => return None,
+        };
+        Some(summary)
+    }
+}
+
+/// Parses a custom DNS record of the form `<ip> <hostname>`. Entries carrying
+/// extra aliases are left alone so they are never clobbered.
+fn parse_host_entry(entry: &str) -> Option<(IpAddr, &str)> {
+    let mut parts = entry.split_whitespace();
+    let address = parts.next()?.parse().ok()?;
+    let name = parts.next()?;
+    parts.next().is_none().then_some((address, name))
+}
+
+impl PiHole {
+    fn endpoint(&self, segments: &[&str]) -> Result<Url> {
+        let mut url = Url::parse(&self.api_url)
+            .map_err(|error| anyhow!("invalid Pi-hole api_url ({}): {error}", self.api_url))?;
+        url.path_segments_mut()
+            .map_err(|()| anyhow!("invalid Pi-hole api_url ({})", self.api_url))?
+            .pop_if_empty()
+            .extend(segments);
+        Ok(url)
+    }
+
+    async fn login(&self, request: &HttpClient) -> Result<Option<SecretString>> {
+        let response = request
+            .post(self.endpoint(&["api", "auth"])?)
+            .json(&json!({ "password": self.password.expose_secret() }))
+            .send()
+            .await?;
+        let response = check_pihole_response(response, "log in to Pi-hole").await?;
+        let auth = response.json::<AuthResponse>().await?;
+        if !auth.session.valid {
+            return Err(anyhow!("failed to log in to Pi-hole: session is not valid"));
+        }
+        // Without a password configured on the Pi-hole no session ID is issued
+        Ok(auth.session.sid)
+    }
+
+    async fn logout(&self, request: &HttpClient, sid: &SecretString) {
+        let result = async {
+            let url = self.endpoint(&["api", "auth"])?;
+            let response = request
+                .delete(url)
+                .header("X-FTL-SID", sid.expose_secret())
+                .send()
+                .await?;
+            check_pihole_response(response, "log out of Pi-hole").await
+        }
+        .await;
+        // Sessions expire on their own, a failed logout only holds a slot until then
+        if let Err(error) = result {
+            warn!("{error}");
+        }
+    }
+
+    async fn send(
+        &self,
+        request: reqwest_middleware::RequestBuilder,
+        sid: Option<&SecretString>,
+        action: &str,
+    ) -> Result<Response> {
+        let request = match sid {
+            Some(sid) => request.header("X-FTL-SID", sid.expose_secret()),
+            None => request,
+        };
+        check_pihole_response(request.send().await?, action).await
+    }
+
+    async fn update_hosts(
+        &self,
+        request: &HttpClient,
+        sid: Option<&SecretString>,
+        update: &IpUpdate,
+    ) -> Result<bool> {
+        let hosts = self
+            .send(
+                request.get(self.endpoint(&["api", "config", "dns", "hosts"])?),
+                sid,
+                "list Pi-hole local DNS records",
+            )
+            .await?
+            .json::<HostsResponse>()
+            .await?
+            .config
+            .dns
+            .hosts;
+
+        let mut changed = false;
+        for domain in &self.domains {
+            for (version, addr) in update.iter() {
+                let stale = hosts
+                    .iter()
+                    .filter_map(|entry| {
+                        let (address, name) = parse_host_entry(entry)?;
+                        (name.eq_ignore_ascii_case(&domain.name)
+                            && address.is_ipv4() == addr.is_ipv4())
+                        .then_some((address, entry))
+                    })
+                    .collect::<Vec<_>>();
+                if let [(address, _)] = stale.as_slice()
+                    && *address == addr
+                {
+                    continue;
+                }
+
+                let action = format!(
+                    "update Pi-hole local DNS record ({}) for {version}",
+                    domain.name
+                );
+                // Add before removing so the name always resolves
+                if !stale.iter().any(|(address, _)| *address == addr) {
+                    let entry = format!("{addr} {}", domain.name);
+                    self.send(
+                        request.put(self.endpoint(&["api", "config", "dns", "hosts", &entry])?),
+                        sid,
+                        &action,
+                    )
+                    .await?;
+                }
+                for (address, entry) in stale {
+                    if address == addr {
+                        continue;
+                    }
+                    self.send(
+                        request.delete(self.endpoint(&["api", "config", "dns", "hosts", entry])?),
+                        sid,
+                        &action,
+                    )
+                    .await?;
+                }
+                changed = true;
+            }
+        }
+        Ok(changed)
+    }
+}
+
+async fn check_pihole_response(response: Response, action: &str) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body = response.text().await?;
+    let detail = serde_json::from_str::<PiHoleError>(&body)
+        .ok()
+        .and_then(PiHoleError::summary)
+        .or_else(|| body_snippet(&body));
+    let detail = detail.map_or_else(String::new, |detail| format!(": {detail}"));
+
+    Err(anyhow!("failed to {action}: HTTP {status}{detail}"))
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[async_trait]
+#[typetag::deserialize(name = "pihole")]
+impl Provider for PiHole {
+    fn validate_config(&self) -> Result<()> {
+        self.endpoint(&[])?;
+        if self.domains.is_empty() {
+            return Err(anyhow!("no domains configured for Pi-hole provider"));
+        }
+        for domain in &self.domains {
+            if domain.name.trim().is_empty() || domain.name.contains(char::is_whitespace) {
+                return Err(anyhow!(
+                    "Pi-hole domain ({}) must be a single non-empty hostname",
+                    domain.name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn update(&self, update: IpUpdate, request: HttpClient) -> Result<bool> {
+        let sid = self.login(&request).await?;
+        let result = self.update_hosts(&request, sid.as_ref(), &update).await;
+        if let Some(sid) = &sid {
+            self.logout(&request, sid).await;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use smallvec::smallvec;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_json, header, method, path},
+    };
+
+    use super::*;
+
+    const UPDATE_BOTH: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+    const UPDATE_V4: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: None,
+    };
+
+    fn provider(api_url: String) -> PiHole {
+        PiHole {
+            api_url,
+            password: "app-password".into(),
+            domains: smallvec![Domain {
+                name: "wan.lan".into(),
+            }],
+        }
+    }
+
+    async fn mount_session(mock: &MockServer) {
+        Mock::given(method("POST"))
+            .and(path("/api/auth"))
+            .and(body_json(json!({"password": "app-password"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "session": {"valid": true, "totp": false, "sid": "session-id", "csrf": "csrf", "validity": 1800}
+            })))
+            .expect(1)
+            .mount(mock)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/auth"))
+            .and(header("X-FTL-SID", "session-id"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(mock)
+            .await;
+    }
+
+    async fn mount_hosts(mock: &MockServer, hosts: &[&str]) {
+        Mock::given(method("GET"))
+            .and(path("/api/config/dns/hosts"))
+            .and(header("X-FTL-SID", "session-id"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "config": {"dns": {"hosts": hosts}}
+            })))
+            .expect(1)
+            .mount(mock)
+            .await;
+    }
+
+    #[test]
+    fn test_pihole_deserializes_from_config() {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+[[providers]]
+type = "pihole"
+api_url = "http://pi.hole"
+password = "app-password"
+
+[[providers.domains]]
+name = "wan.lan"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.providers.len(), 1);
+        config.providers[0].validate_config().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pihole_replaces_stale_records() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        mount_session(&mock).await;
+        mount_hosts(
+            &mock,
+            &[
+                "192.0.2.1 wan.lan",
+                "192.0.2.1 nas.lan",
+                "10.0.0.1 wan.lan router.lan",
+            ],
+        )
+        .await;
+        Mock::given(method("PUT"))
+            .and(path("/api/config/dns/hosts/127.0.0.1%20wan.lan"))
+            .and(header("X-FTL-SID", "session-id"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/api/config/dns/hosts/::1%20wan.lan"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/config/dns/hosts/192.0.2.1%20wan.lan"))
+            .and(header("X-FTL-SID", "session-id"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_BOTH, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_pihole_skips_current_records() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        mount_session(&mock).await;
+        mount_hosts(&mock, &["127.0.0.1 WAN.lan", "::1 wan.lan"]).await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(0)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_V4, http).await.unwrap();
+        assert!(!result);
+    }
+
+    #[tokio::test]
+    async fn test_pihole_bad_password() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("POST"))
+            .and(path("/api/auth"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+                "error": {"key": "unauthorized", "message": "Unauthorized", "hint": null},
+                "took": 0.003
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to log in to Pi-hole: HTTP 401 Unauthorized: Unauthorized"
+        );
+    }
+
+    #[test]
+    fn test_pihole_validates_config() {
+        let mut provider = provider("http://pi.hole".to_string());
+        provider.validate_config().unwrap();
+
+        provider.domains[0].name = "wan lan".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "Pi-hole domain (wan lan) must be a single non-empty hostname"
+        );
+
+        provider.api_url = "pi.hole".to_string();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "invalid Pi-hole api_url (pi.hole): relative URL without a base"
+        );
+    }
+}
@@ -0,0 +1,368 @@
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use compact_str::CompactString;
+use reqwest::Response;
+use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use smallvec::SmallVec;
+use tracing::info;
+
+use crate::ip::{IpUpdate, IpVersion};
+use crate::providers::Provider;
+
+/// `PowerDNS` Authoritative HTTP API update provider
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct PowerDns {
+    api_url: String,
+    api_key: SecretString,
+    #[serde(default = "default_server_id")]
+    server_id: CompactString,
+    zone: CompactString,
+    #[serde(default)]
+    notify: bool,
+    domains: SmallVec<[Domain; 2]>,
+}
+
+fn default_server_id() -> CompactString {
+    "localhost".into()
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+struct Domain {
+    name: CompactString,
+    #[serde(default = "default_ttl")]
+    ttl: u32,
+}
+
+fn default_ttl() -> u32 {
+    300
+}
+
+#[derive(Debug, Deserialize)]
+struct PowerDnsError {
+    error: Option<String>,
+}
+
+/// Canonical absolute form of a name, with a single trailing dot.
+fn canonical(name: &str) -> String {
+    format!("{}.", name.trim_end_matches('.'))
+}
+
+impl PowerDns {
+    fn zone_url(&self) -> String {
+        format!(
+            "{}/api/v1/servers/{}/zones/{}",
+            self.api_url.trim_end_matches('/'),
+            self.server_id,
+            canonical(&self.zone)
+        )
+    }
+
+    fn rrsets(&self, update: &IpUpdate) -> Vec<Value> {
+        let mut rrsets = Vec::new();
+        for domain in &self.domains {
+            for (version, addr) in update.iter() {
+                let record_type = match version {
+                    IpVersion::V4 => "A",
+                    IpVersion::V6 => "AAAA",
+                };
+                rrsets.push(json!({
+                    "name": canonical(&domain.name),
+                    "type": record_type,
+                    "ttl": domain.ttl,
+                    "changetype": "REPLACE",
+                    "records": [{ "content": addr.to_string(), "disabled": false }],
+                }));
+            }
+        }
+        rrsets
+    }
+
+    async fn patch_zone(&self, request: &HttpClient, update: &IpUpdate) -> Result<()> {
+        let response = request
+            .patch(self.zone_url())
+            .header("X-API-Key", self.api_key.expose_secret())
+            .json(&json!({ "rrsets": self.rrsets(update) }))
+            .send()
+            .await?;
+        parse_powerdns_response(
+            response,
+            &format!("update PowerDNS zone ({})", canonical(&self.zone)),
+        )
+        .await
+    }
+
+    async fn notify_secondaries(&self, request: &HttpClient) -> Result<()> {
+        let response = request
+            .put(format!("{}/notify", self.zone_url()))
+            .header("X-API-Key", self.api_key.expose_secret())
+            .send()
+            .await?;
+        parse_powerdns_response(
+            response,
+            &format!(
+                "notify secondaries of PowerDNS zone ({})",
+                canonical(&self.zone)
+            ),
+        )
+        .await?;
+        info!("queued NOTIFY for PowerDNS zone {}", canonical(&self.zone));
+        Ok(())
+    }
+}
+
+async fn parse_powerdns_response(response: Response, action: &str) -> Result<()> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+
+    let body = response.text().await?;
+    let detail = serde_json::from_str::<PowerDnsError>(&body)
+        .ok()
+        .and_then(|error| error.error)
+        .filter(|error| !error.is_empty())
+        .or_else(|| body_snippet(&body));
+    let detail = detail.map_or_else(String::new, |detail| format!(": {detail}"));
+
+    Err(anyhow!("failed to {action}: HTTP {status}{detail}"))
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[async_trait]
+#[typetag::deserialize(name = "powerdns")]
+impl Provider for PowerDns {
+    fn validate_config(&self) -> Result<()> {
+        if self.api_url.trim().is_empty() {
+            return Err(anyhow!("PowerDNS api_url must not be empty"));
+        }
+        if self.api_key.expose_secret().trim().is_empty() {
+            return Err(anyhow!("PowerDNS api_key must not be empty"));
+        }
+        if self.server_id.trim().is_empty() {
+            return Err(anyhow!("PowerDNS server_id must not be empty"));
+        }
+        if self.zone.trim_end_matches('.').trim().is_empty() {
+            return Err(anyhow!("PowerDNS zone must not be empty"));
+        }
+        if self.domains.is_empty() {
+            return Err(anyhow!("no domains configured for PowerDNS provider"));
+        }
+
+        let zone = canonical(&self.zone).to_ascii_lowercase();
+        for domain in &self.domains {
+            let name = canonical(&domain.name).to_ascii_lowercase();
+            if name != zone && !name.ends_with(&format!(".{zone}")) {
+                return Err(anyhow!(
+                    "PowerDNS domain ({}) is not within {}",
+                    domain.name,
+                    self.zone
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn update(&self, update: IpUpdate, request: HttpClient) -> Result<bool> {
+        if update.is_empty() {
+            return Err(anyhow!("no IP addresses supplied for PowerDNS update"));
+        }
+
+        self.patch_zone(&request, &update).await?;
+        if self.notify {
+            self.notify_secondaries(&request).await?;
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use smallvec::smallvec;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_json, header, method, path},
+    };
+
+    use super::*;
+
+    const UPDATE_BOTH: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+    const UPDATE_V4: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: None,
+    };
+
+    fn provider(api_url: String) -> PowerDns {
+        PowerDns {
+            api_url,
+            api_key: "secret".into(),
+            server_id: default_server_id(),
+            zone: "example.com".into(),
+            notify: false,
+            domains: smallvec![
+                Domain {
+                    name: "example.com".into(),
+                    ttl: 300,
+                },
+                Domain {
+                    name: "www.example.com.".into(),
+                    ttl: 60,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_powerdns_deserializes_from_config() {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+[[providers]]
+type = "powerdns"
+api_url = "http://127.0.0.1:8081"
+api_key = "secret"
+zone = "example.com."
+notify = true
+
+[[providers.domains]]
+name = "home.example.com"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.providers.len(), 1);
+        config.providers[0].validate_config().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_powerdns_replaces_rrsets_in_one_patch() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("PATCH"))
+            .and(path("/api/v1/servers/localhost/zones/example.com."))
+            .and(header("X-API-Key", "secret"))
+            .and(body_json(json!({
+                "rrsets": [
+                    {"name": "example.com.", "type": "A", "ttl": 300, "changetype": "REPLACE",
+                     "records": [{"content": "127.0.0.1", "disabled": false}]},
+                    {"name": "example.com.", "type": "AAAA", "ttl": 300, "changetype": "REPLACE",
+                     "records": [{"content": "::1", "disabled": false}]},
+                    {"name": "www.example.com.", "type": "A", "ttl": 60, "changetype": "REPLACE",
+                     "records": [{"content": "127.0.0.1", "disabled": false}]},
+                    {"name": "www.example.com.", "type": "AAAA", "ttl": 60, "changetype": "REPLACE",
+                     "records": [{"content": "::1", "disabled": false}]}
+                ]
+            })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_BOTH, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_powerdns_notifies_secondaries() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let mut provider = provider(mock.uri());
+        provider.notify = true;
+
+        Mock::given(method("PATCH"))
+            .and(path("/api/v1/servers/localhost/zones/example.com."))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/api/v1/servers/localhost/zones/example.com./notify"))
+            .and(header("X-API-Key", "secret"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({"result": "Notification queued"})),
+            )
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_V4, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_powerdns_surfaces_errors() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("PATCH"))
+            .respond_with(ResponseTemplate::new(422).set_body_json(json!({
+                "error": "RRset www.example.com. IN A: Conflicts with pre-existing RRset"
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to update PowerDNS zone (example.com.): HTTP 422 Unprocessable Entity: RRset www.example.com. IN A: Conflicts with pre-existing RRset"
+        );
+    }
+
+    #[test]
+    fn test_powerdns_validates_config() {
+        let mut provider = provider("http://127.0.0.1:8081".to_string());
+        provider.validate_config().unwrap();
+
+        provider.domains[1].name = "www.example.org.".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "PowerDNS domain (www.example.org.) is not within example.com"
+        );
+
+        provider.api_key = "".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "PowerDNS api_key must not be empty"
+        );
+    }
+}
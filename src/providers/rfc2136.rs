@@ -0,0 +1,1023 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::ops::Range;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, anyhow};
+use async_trait::async_trait;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use compact_str::CompactString;
+use hmac::{Hmac, Mac};
+use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use sha2::{Sha256, Sha512};
+use smallvec::SmallVec;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket, lookup_host};
+use tokio::time;
+use tracing::debug;
+
+use crate::ip::{IpUpdate, IpVersion};
+use crate::providers::Provider;
+
+const HEADER_LEN: usize = 12;
+const MAX_UDP_MESSAGE_BYTES: usize = 512;
+const MAX_NAME_BYTES: usize = 255;
+const MAX_LABEL_BYTES: usize = 63;
+const MAX_POINTER_JUMPS: usize = 16;
+
+const OPCODE_UPDATE: u16 = 5 << 11;
+const FLAG_RESPONSE: u16 = 1 << 15;
+const FLAG_TRUNCATED: u16 = 1 << 9;
+
+const TYPE_A: u16 = 1;
+const TYPE_SOA: u16 = 6;
+const TYPE_AAAA: u16 = 28;
+const TYPE_TSIG: u16 = 250;
+const CLASS_IN: u16 = 1;
+const CLASS_ANY: u16 = 255;
+
+// RFC 8945 recommends a fudge of 300 seconds
+const TSIG_FUDGE: u16 = 300;
+
+/// RFC 2136 dynamic DNS UPDATE provider
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Rfc2136 {
+    server: CompactString,
+    #[serde(default = "default_port")]
+    port: u16,
+    zone: CompactString,
+    key_name: CompactString,
+    #[serde(default)]
+    key_algorithm: TsigAlgorithm,
+    key_secret: SecretString,
+    #[serde(default = "default_timeout", with = "humantime_serde")]
+    timeout: Duration,
+    domains: SmallVec<[Domain; 2]>,
+}
+
+fn default_port() -> u16 {
+    53
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(5)
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct Domain {
+    name: CompactString,
+    #[serde(default = "default_ttl")]
+    ttl: u32,
+}
+
+fn default_ttl() -> u32 {
+    300
+}
+
+/// TSIG HMAC algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+enum TsigAlgorithm {
+    #[default]
+    #[serde(rename = "hmac-sha256")]
+    HmacSha256,
+    #[serde(rename = "hmac-sha512")]
+    HmacSha512,
+}
+
+impl TsigAlgorithm {
+    fn name(self) -> &'static str {
+        match self {
+            Self::HmacSha256 => "hmac-sha256",
+            Self::HmacSha512 => "hmac-sha512",
+        }
+    }
+}
+
+/// Decoded TSIG key used to sign requests and verify responses
+struct TsigKey {
+    name: String,
+    algorithm: TsigAlgorithm,
+    secret: Vec<u8>,
+}
+
+impl TsigKey {
+    fn mac(&self, data: &[u8]) -> Vec<u8> {
+        match self.algorithm {
+            TsigAlgorithm::HmacSha256 => {
+                let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.secret)
+                    .expect("HMAC accepts keys of any length");
+                mac.update(data);
+                mac.finalize().into_bytes().to_vec()
+            }
+            TsigAlgorithm::HmacSha512 => {
+                let mut mac = <Hmac<Sha512> as Mac>::new_from_slice(&self.secret)
+                    .expect("HMAC accepts keys of any length");
+                mac.update(data);
+                mac.finalize().into_bytes().to_vec()
+            }
+        }
+    }
+
+    /// Checks a MAC in constant time.
+    fn verify(&self, data: &[u8], expected: &[u8]) -> bool {
+        match self.algorithm {
+            TsigAlgorithm::HmacSha256 => {
+                let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.secret)
+                    .expect("HMAC accepts keys of any length");
+                mac.update(data);
+                mac.verify_slice(expected).is_ok()
+            }
+            TsigAlgorithm::HmacSha512 => {
+                let mut mac = <Hmac<Sha512> as Mac>::new_from_slice(&self.secret)
+                    .expect("HMAC accepts keys of any length");
+                mac.update(data);
+                mac.verify_slice(expected).is_ok()
+            }
+        }
+    }
+}
+
+/// TSIG resource record fields
+#[derive(Debug)]
+struct Tsig {
+    algorithm: String,
+    time_signed: u64,
+    fudge: u16,
+    mac: Vec<u8>,
+    original_id: u16,
+    error: u16,
+}
+
+/// Resource record located within a parsed message
+#[derive(Debug)]
+struct ResourceRecord {
+    offset: usize,
+    name: String,
+    record_type: u16,
+    class: u16,
+    ttl: u32,
+    rdata: Range<usize>,
+}
+
+/// Minimal view of a DNS message
+#[derive(Debug)]
+struct Message {
+    id: u16,
+    flags: u16,
+    records: Vec<ResourceRecord>,
+}
+
+impl Message {
+    fn rcode(&self) -> u16 {
+        self.flags & 0x000f
+    }
+
+    fn tsig(&self, bytes: &[u8]) -> Result<Option<(&ResourceRecord, Tsig)>> {
+        let Some(record) = self
+            .records
+            .last()
+            .filter(|record| record.record_type == TYPE_TSIG)
+        else {
+            return Ok(None);
+        };
+        if record.class != CLASS_ANY || record.ttl != 0 {
+            return Err(anyhow!("malformed TSIG record"));
+        }
+        Ok(Some((record, parse_tsig_rdata(bytes, &record.rdata)?)))
+    }
+}
+
+impl Rfc2136 {
+    fn tsig_key(&self) -> Result<TsigKey> {
+        let secret = BASE64
+            .decode(self.key_secret.expose_secret().trim())
+            .context("RFC 2136 key_secret must be valid base64")?;
+        Ok(TsigKey {
+            name: normalize_name(&self.key_name),
+            algorithm: self.key_algorithm,
+            secret,
+        })
+    }
+
+    fn build_update_message(&self, id: u16, update: &IpUpdate) -> Result<Vec<u8>> {
+        let mut updates = 0u16;
+        let mut body = Vec::new();
+        for domain in &self.domains {
+            for (version, addr) in update.iter() {
+                let record_type = match version {
+                    IpVersion::V4 => TYPE_A,
+                    IpVersion::V6 => TYPE_AAAA,
+                };
+                // Delete the existing RRset before adding the replacement record
+                push_name(&mut body, &domain.name)?;
+                push_u16(&mut body, record_type);
+                push_u16(&mut body, CLASS_ANY);
+                push_u32(&mut body, 0);
+                push_u16(&mut body, 0);
+
+                let rdata = match addr {
+                    IpAddr::V4(addr) => addr.octets().to_vec(),
+                    IpAddr::V6(addr) => addr.octets().to_vec(),
+                };
+                push_name(&mut body, &domain.name)?;
+                push_u16(&mut body, record_type);
+                push_u16(&mut body, CLASS_IN);
+                push_u32(&mut body, domain.ttl);
+                push_u16(&mut body, u16::try_from(rdata.len())?);
+                body.extend_from_slice(&rdata);
+
+                updates = updates
+                    .checked_add(2)
+                    .ok_or_else(|| anyhow!("too many RFC 2136 updates for a single message"))?;
+            }
+        }
+
+        let mut message = Vec::with_capacity(HEADER_LEN + body.len());
+        push_u16(&mut message, id);
+        push_u16(&mut message, OPCODE_UPDATE);
+        push_u16(&mut message, 1);
+        push_u16(&mut message, 0);
+        push_u16(&mut message, updates);
+        push_u16(&mut message, 0);
+        push_name(&mut message, &self.zone)?;
+        push_u16(&mut message, TYPE_SOA);
+        push_u16(&mut message, CLASS_IN);
+        message.extend_from_slice(&body);
+        Ok(message)
+    }
+
+    async fn resolve_server(&self) -> Result<SocketAddr> {
+        lookup_host((self.server.as_str(), self.port))
+            .await
+            .with_context(|| format!("failed to resolve RFC 2136 server {}", self.server))?
+            .next()
+            .ok_or_else(|| anyhow!("no addresses found for RFC 2136 server {}", self.server))
+    }
+
+    async fn exchange(&self, server: SocketAddr, message: &[u8], id: u16) -> Result<Vec<u8>> {
+        if message.len() <= MAX_UDP_MESSAGE_BYTES {
+            let response = time::timeout(self.timeout, exchange_udp(server, message, id))
+                .await
+                .with_context(|| format!("RFC 2136 UDP request to {server} timed out"))??;
+            if read_u16(&response, 2)? & FLAG_TRUNCATED == 0 {
+                return Ok(response);
+            }
+            debug!("RFC 2136 UDP response from {server} was truncated, retrying over TCP");
+        }
+        time::timeout(self.timeout, exchange_tcp(server, message))
+            .await
+            .with_context(|| format!("RFC 2136 TCP request to {server} timed out"))?
+    }
+}
+
+#[async_trait]
+#[typetag::deserialize(name = "rfc2136")]
+impl Provider for Rfc2136 {
+    fn validate_config(&self) -> Result<()> {
+        if self.server.trim().is_empty() {
+            return Err(anyhow!("RFC 2136 server must not be empty"));
+        }
+        if self.key_name.trim().is_empty() {
+            return Err(anyhow!("RFC 2136 key_name must not be empty"));
+        }
+        validate_name(&self.key_name)?;
+        validate_name(&self.zone)?;
+        if self.tsig_key()?.secret.is_empty() {
+            return Err(anyhow!("RFC 2136 key_secret must not be empty"));
+        }
+        if self.timeout.is_zero() {
+            return Err(anyhow!("RFC 2136 timeout must be greater than 0s"));
+        }
+        if self.domains.is_empty() {
+            return Err(anyhow!("no domains configured for RFC 2136 provider"));
+        }
+        let zone = normalize_name(&self.zone);
+        for domain in &self.domains {
+            validate_name(&domain.name)?;
+            let name = normalize_name(&domain.name);
+            if name != zone && !name.ends_with(&format!(".{zone}")) {
+                return Err(anyhow!(
+                    "RFC 2136 domain ({}) is not within zone ({})",
+                    domain.name,
+                    self.zone
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn update(&self, update: IpUpdate, _request: HttpClient) -> Result<bool> {
+        if update.is_empty() {
+            return Err(anyhow!("no IP addresses supplied for RFC 2136 update"));
+        }
+        self.validate_config()?;
+
+        let key = self.tsig_key()?;
+        let id = random_id();
+        let mut message = self.build_update_message(id, &update)?;
+        let request_mac = sign_message(&mut message, &key, None, unix_time()?)?;
+
+        let server = self.resolve_server().await?;
+        let response = self.exchange(server, &message, id).await?;
+        check_update_response(&response, id, &key, &request_mac)
+            .with_context(|| format!("failed to update RFC 2136 zone ({})", self.zone))?;
+        Ok(true)
+    }
+}
+
+async fn exchange_udp(server: SocketAddr, message: &[u8], id: u16) -> Result<Vec<u8>> {
+    let bind: SocketAddr = match server {
+        SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+        SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+    };
+    let socket = UdpSocket::bind(bind).await?;
+    socket.connect(server).await?;
+    socket.send(message).await?;
+
+    let mut buf = vec![0; usize::from(u16::MAX)];
+    loop {
+        let len = socket.recv(&mut buf).await?;
+        // Ignore stray datagrams that don't belong to this request
+        if len >= HEADER_LEN && read_u16(&buf, 0)? == id {
+            buf.truncate(len);
+            return Ok(buf);
+        }
+    }
+}
+
+async fn exchange_tcp(server: SocketAddr, message: &[u8]) -> Result<Vec<u8>> {
+    let mut stream = TcpStream::connect(server).await?;
+    let len = u16::try_from(message.len())
+        .map_err(|_| anyhow!("RFC 2136 message exceeded {} bytes", u16::MAX))?;
+    let mut framed = Vec::with_capacity(message.len() + 2);
+    push_u16(&mut framed, len);
+    framed.extend_from_slice(message);
+    stream.write_all(&framed).await?;
+
+    let len = stream.read_u16().await?;
+    let mut response = vec![0; usize::from(len)];
+    stream.read_exact(&mut response).await?;
+    Ok(response)
+}
+
+/// Appends a TSIG record to the message and returns its MAC.
+fn sign_message(
+    message: &mut Vec<u8>,
+    key: &TsigKey,
+    request_mac: Option<&[u8]>,
+    time_signed: u64,
+) -> Result<Vec<u8>> {
+    let original_id = read_u16(message, 0)?;
+    let mac = tsig_mac(key, message, request_mac, time_signed, TSIG_FUDGE, 0)?;
+
+    let mut rdata = Vec::new();
+    push_name(&mut rdata, key.algorithm.name())?;
+    push_u48(&mut rdata, time_signed)?;
+    push_u16(&mut rdata, TSIG_FUDGE);
+    push_u16(&mut rdata, u16::try_from(mac.len())?);
+    rdata.extend_from_slice(&mac);
+    push_u16(&mut rdata, original_id);
+    push_u16(&mut rdata, 0);
+    push_u16(&mut rdata, 0);
+
+    push_name(message, &key.name)?;
+    push_u16(message, TYPE_TSIG);
+    push_u16(message, CLASS_ANY);
+    push_u32(message, 0);
+    push_u16(message, u16::try_from(rdata.len())?);
+    message.extend_from_slice(&rdata);
+
+    let additional = read_u16(message, 10)?
+        .checked_add(1)
+        .ok_or_else(|| anyhow!("too many additional records in RFC 2136 message"))?;
+    message[10..12].copy_from_slice(&additional.to_be_bytes());
+    Ok(mac)
+}
+
+/// Computes the RFC 8945 MAC over a message without its TSIG record.
+fn tsig_mac(
+    key: &TsigKey,
+    message: &[u8],
+    request_mac: Option<&[u8]>,
+    time_signed: u64,
+    fudge: u16,
+    error: u16,
+) -> Result<Vec<u8>> {
+    let data = tsig_data(key, message, request_mac, time_signed, fudge, error)?;
+    Ok(key.mac(&data))
+}
+
+/// Builds the RFC 8945 data covered by the MAC of a message.
+fn tsig_data(
+    key: &TsigKey,
+    message: &[u8],
+    request_mac: Option<&[u8]>,
+    time_signed: u64,
+    fudge: u16,
+    error: u16,
+) -> Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(message.len() + 128);
+    if let Some(request_mac) = request_mac {
+        push_u16(&mut data, u16::try_from(request_mac.len())?);
+        data.extend_from_slice(request_mac);
+    }
+    data.extend_from_slice(message);
+    push_name(&mut data, &key.name)?;
+    push_u16(&mut data, CLASS_ANY);
+    push_u32(&mut data, 0);
+    push_name(&mut data, key.algorithm.name())?;
+    push_u48(&mut data, time_signed)?;
+    push_u16(&mut data, fudge);
+    push_u16(&mut data, error);
+    push_u16(&mut data, 0);
+    Ok(data)
+}
+
+fn check_update_response(
+    response: &[u8],
+    id: u16,
+    key: &TsigKey,
+    request_mac: &[u8],
+) -> Result<()> {
+    let message = parse_message(response)?;
+    if message.id != id {
+        return Err(anyhow!("response ID did not match request"));
+    }
+    if message.flags & FLAG_RESPONSE == 0 {
+        return Err(anyhow!("server sent a query instead of a response"));
+    }
+    let tsig = message.tsig(response)?;
+
+    if message.rcode() != 0 {
+        let detail = tsig
+            .as_ref()
+            .filter(|(_record, tsig)| tsig.error != 0)
+            .map_or_else(String::new, |(_record, tsig)| {
+                format!(" (TSIG {})", rcode_name(tsig.error))
+            });
+        return Err(anyhow!(
+            "server returned {}{detail}",
+            rcode_name(message.rcode())
+        ));
+    }
+
+    let Some((record, tsig)) = tsig else {
+        return Err(anyhow!("server response was not TSIG signed"));
+    };
+    if normalize_name(&record.name) != key.name {
+        return Err(anyhow!("server response was signed with an unexpected key"));
+    }
+    if normalize_name(&tsig.algorithm) != key.algorithm.name() {
+        return Err(anyhow!(
+            "server response was signed with an unexpected algorithm ({})",
+            tsig.algorithm
+        ));
+    }
+    if tsig.error != 0 {
+        return Err(anyhow!(
+            "server returned TSIG error {}",
+            rcode_name(tsig.error)
+        ));
+    }
+
+    // The MAC covers the response as it was before the TSIG record was appended
+    let mut unsigned = response[..record.offset].to_vec();
+    unsigned[0..2].copy_from_slice(&tsig.original_id.to_be_bytes());
+    let additional = read_u16(&unsigned, 10)?.saturating_sub(1);
+    unsigned[10..12].copy_from_slice(&additional.to_be_bytes());
+    let data = tsig_data(
+        key,
+        &unsigned,
+        Some(request_mac),
+        tsig.time_signed,
+        tsig.fudge,
+        tsig.error,
+    )?;
+    if !key.verify(&data, &tsig.mac) {
+        return Err(anyhow!("server response TSIG signature did not verify"));
+    }
+    if unix_time()?.abs_diff(tsig.time_signed) > u64::from(tsig.fudge) {
+        return Err(anyhow!(
+            "server response TSIG time is outside the fudge window"
+        ));
+    }
+    Ok(())
+}
+
+fn parse_message(bytes: &[u8]) -> Result<Message> {
+    if bytes.len() < HEADER_LEN {
+        return Err(anyhow!("DNS message shorter than header"));
+    }
+    let id = read_u16(bytes, 0)?;
+    let flags = read_u16(bytes, 2)?;
+    let questions = read_u16(bytes, 4)?;
+    let record_count = [6, 8, 10]
+        .into_iter()
+        .map(|offset| read_u16(bytes, offset).map(usize::from))
+        .sum::<Result<usize>>()?;
+
+    let mut offset = HEADER_LEN;
+    for _ in 0..questions {
+        let (_name, next) = read_name(bytes, offset)?;
+        offset = next + 4;
+    }
+
+    let mut records = Vec::with_capacity(record_count);
+    for _ in 0..record_count {
+        let start = offset;
+        let (name, next) = read_name(bytes, offset)?;
+        let record_type = read_u16(bytes, next)?;
+        let class = read_u16(bytes, next + 2)?;
+        let ttl = read_u32(bytes, next + 4)?;
+        let rdata_len = usize::from(read_u16(bytes, next + 8)?);
+        let rdata = next + 10..next + 10 + rdata_len;
+        if rdata.end > bytes.len() {
+            return Err(anyhow!("DNS record data exceeded message length"));
+        }
+        offset = rdata.end;
+        records.push(ResourceRecord {
+            offset: start,
+            name,
+            record_type,
+            class,
+            ttl,
+            rdata,
+        });
+    }
+
+    Ok(Message { id, flags, records })
+}
+
+fn parse_tsig_rdata(bytes: &[u8], rdata: &Range<usize>) -> Result<Tsig> {
+    let (algorithm, offset) = read_name(bytes, rdata.start)?;
+    let time_signed =
+        (u64::from(read_u16(bytes, offset)?) << 32) | u64::from(read_u32(bytes, offset + 2)?);
+    let fudge = read_u16(bytes, offset + 6)?;
+    let mac_len = usize::from(read_u16(bytes, offset + 8)?);
+    let mac_start = offset + 10;
+    let mac = bytes
+        .get(mac_start..mac_start + mac_len)
+        .ok_or_else(|| anyhow!("TSIG MAC exceeded message length"))?
+        .to_vec();
+    let original_id = read_u16(bytes, mac_start + mac_len)?;
+    let error = read_u16(bytes, mac_start + mac_len + 2)?;
+    if mac_start + mac_len + 6 > rdata.end {
+        return Err(anyhow!("TSIG record data was truncated"));
+    }
+    Ok(Tsig {
+        algorithm,
+        time_signed,
+        fudge,
+        mac,
+        original_id,
+        error,
+    })
+}
+
+fn read_name(bytes: &[u8], mut offset: usize) -> Result<(String, usize)> {
+    let mut name = String::new();
+    let mut end = None;
+    let mut jumps = 0;
+    loop {
+        let len = *bytes
+            .get(offset)
+            .ok_or_else(|| anyhow!("DNS name exceeded message length"))?;
+        match len {
+            0 => {
+                return Ok((name, end.unwrap_or(offset + 1)));
+            }
+            len if len & 0xc0 == 0xc0 => {
+                jumps += 1;
+                if jumps > MAX_POINTER_JUMPS {
+                    return Err(anyhow!("DNS name compression loop detected"));
+                }
+                let pointer = usize::from(read_u16(bytes, offset)? & 0x3fff);
+                end.get_or_insert(offset + 2);
+                offset = pointer;
+            }
+            len if len & 0xc0 == 0 => {
+                let label = bytes
+                    .get(offset + 1..offset + 1 + usize::from(len))
+                    .ok_or_else(|| anyhow!("DNS label exceeded message length"))?;
+                if !name.is_empty() {
+                    name.push('.');
+                }
+                name.push_str(&String::from_utf8_lossy(label));
+                offset += 1 + usize::from(len);
+            }
+            _ => return Err(anyhow!("unsupported DNS label type")),
+        }
+    }
+}
+
+fn rcode_name(rcode: u16) -> String {
+    match rcode {
+        0 => "NOERROR".to_string(),
+        1 => "FORMERR".to_string(),
+        2 => "SERVFAIL".to_string(),
+        3 => "NXDOMAIN".to_string(),
+        4 => "NOTIMP".to_string(),
+        5 => "REFUSED".to_string(),
+        6 => "YXDOMAIN".to_string(),
+        7 => "YXRRSET".to_string(),
+        8 => "NXRRSET".to_string(),
+        9 => "NOTAUTH".to_string(),
+        10 => "NOTZONE".to_string(),
+        16 => "BADSIG".to_string(),
+        17 => "BADKEY".to_string(),
+        18 => "BADTIME".to_string(),
+        rcode => format!("RCODE {rcode}"),
+    }
+}
+
+fn normalize_name(name: &str) -> String {
+    name.trim_end_matches('.').to_ascii_lowercase()
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let mut encoded = Vec::new();
+    push_name(&mut encoded, name)?;
+    if name.trim_end_matches('.').is_empty() {
+        return Err(anyhow!("RFC 2136 names must not be empty"));
+    }
+    Ok(())
+}
+
+fn push_name(buf: &mut Vec<u8>, name: &str) -> Result<()> {
+    let start = buf.len();
+    let trimmed = name.trim_end_matches('.');
+    if !trimmed.is_empty() {
+        for label in trimmed.split('.') {
+            let len = u8::try_from(label.len())
+                .ok()
+                .filter(|len| (1..=MAX_LABEL_BYTES).contains(&usize::from(*len)))
+                .ok_or_else(|| anyhow!("invalid DNS label in name ({name})"))?;
+            buf.push(len);
+            buf.extend_from_slice(label.as_bytes());
+        }
+    }
+    buf.push(0);
+    if buf.len() - start > MAX_NAME_BYTES {
+        return Err(anyhow!("DNS name ({name}) exceeded {MAX_NAME_BYTES} bytes"));
+    }
+    Ok(())
+}
+
+fn push_u16(buf: &mut Vec<u8>, value: u16) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+fn push_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+fn push_u48(buf: &mut Vec<u8>, value: u64) -> Result<()> {
+    if value >> 48 != 0 {
+        return Err(anyhow!("TSIG time exceeded 48 bits"));
+    }
+    buf.extend_from_slice(&value.to_be_bytes()[2..]);
+    Ok(())
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| anyhow!("DNS message truncated"))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| anyhow!("DNS message truncated"))
+}
+
+fn random_id() -> u16 {
+    let bytes = RandomState::new().hash_one(SystemTime::now()).to_le_bytes();
+    u16::from_le_bytes([bytes[0], bytes[1]])
+}
+
+fn unix_time() -> Result<u64> {
+    Ok(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .context("system clock is before the Unix epoch")?
+        .as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use smallvec::smallvec;
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    use super::*;
+
+    const SECRET: &str = "c2VjcmV0LWtleS1mb3ItdGVzdGluZy1vbmx5";
+
+    const UPDATE_BOTH: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+    const UPDATE_V4: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: None,
+    };
+
+    fn provider(port: u16, algorithm: TsigAlgorithm) -> Rfc2136 {
+        Rfc2136 {
+            server: "127.0.0.1".into(),
+            port,
+            zone: "example.com".into(),
+            key_name: "ddrs-key".into(),
+            key_algorithm: algorithm,
+            key_secret: SECRET.into(),
+            timeout: Duration::from_secs(2),
+            domains: smallvec![
+                Domain {
+                    name: "example.com".into(),
+                    ttl: 60,
+                },
+                Domain {
+                    name: "home.example.com.".into(),
+                    ttl: 300,
+                },
+            ],
+        }
+    }
+
+    fn http() -> HttpClient {
+        ClientBuilder::new(InnerHttpClient::new()).build()
+    }
+
+    /// Verifies the request signature and returns its MAC and update records.
+    fn verify_request(request: &[u8], key: &TsigKey) -> (Vec<u8>, Message) {
+        let message = parse_message(request).unwrap();
+        assert_eq!(message.flags, OPCODE_UPDATE);
+        let (record, tsig) = message.tsig(request).unwrap().expect("request is signed");
+        let mut unsigned = request[..record.offset].to_vec();
+        unsigned[10..12].copy_from_slice(&0u16.to_be_bytes());
+        let expected = tsig_mac(key, &unsigned, None, tsig.time_signed, tsig.fudge, 0).unwrap();
+        assert_eq!(expected, tsig.mac);
+        (tsig.mac, message)
+    }
+
+    fn response(request: &[u8], key: &TsigKey, response_key: &TsigKey, flags: u16) -> Vec<u8> {
+        let (request_mac, _message) = verify_request(request, key);
+        let mut response = Vec::new();
+        response.extend_from_slice(&request[0..2]);
+        push_u16(&mut response, FLAG_RESPONSE | OPCODE_UPDATE | flags);
+        response.extend_from_slice(&[0; 8]);
+        sign_message(
+            &mut response,
+            response_key,
+            Some(&request_mac),
+            unix_time().unwrap(),
+        )
+        .unwrap();
+        response
+    }
+
+    async fn udp_server(
+        key: TsigKey,
+        response_key: TsigKey,
+        flags: u16,
+    ) -> (u16, JoinHandle<Vec<u8>>) {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        let handle = tokio::spawn(async move {
+            let mut buf = vec![0; 4096];
+            let (len, peer) = socket.recv_from(&mut buf).await.unwrap();
+            buf.truncate(len);
+            let response = response(&buf, &key, &response_key, flags);
+            socket.send_to(&response, peer).await.unwrap();
+            buf
+        });
+        (port, handle)
+    }
+
+    fn test_key(algorithm: TsigAlgorithm) -> TsigKey {
+        provider(0, algorithm).tsig_key().unwrap()
+    }
+
+    #[test]
+    fn test_rfc2136_deserializes_from_config() {
+        let config = toml::from_str::<crate::config::Config>(&format!(
+            r#"
+[[providers]]
+type = "rfc2136"
+server = "ns1.example.com"
+zone = "example.com"
+key_name = "ddrs-key"
+key_algorithm = "hmac-sha512"
+key_secret = "{SECRET}"
+
+[[providers.domains]]
+name = "home.example.com"
+ttl = 120
+"#
+        ))
+        .unwrap();
+
+        assert_eq!(config.providers.len(), 1);
+        config.providers[0].validate_config().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rfc2136_update_both() {
+        let key = test_key(TsigAlgorithm::HmacSha256);
+        let (port, server) = udp_server(
+            test_key(TsigAlgorithm::HmacSha256),
+            test_key(TsigAlgorithm::HmacSha256),
+            0,
+        )
+        .await;
+        let provider = provider(port, TsigAlgorithm::HmacSha256);
+
+        let result = provider.update(UPDATE_BOTH, http()).await.unwrap();
+        assert!(result);
+
+        let request = server.await.unwrap();
+        let (_mac, message) = verify_request(&request, &key);
+        let updates = message
+            .records
+            .iter()
+            .filter(|record| record.record_type != TYPE_TSIG)
+            .map(|record| {
+                (
+                    record.name.as_str(),
+                    record.record_type,
+                    record.class,
+                    record.ttl,
+                    &request[record.rdata.clone()],
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            updates,
+            [
+                ("example.com", TYPE_A, CLASS_ANY, 0, &[][..]),
+                ("example.com", TYPE_A, CLASS_IN, 60, &[127, 0, 0, 1][..]),
+                ("example.com", TYPE_AAAA, CLASS_ANY, 0, &[][..]),
+                (
+                    "example.com",
+                    TYPE_AAAA,
+                    CLASS_IN,
+                    60,
+                    &Ipv6Addr::LOCALHOST.octets()[..]
+                ),
+                ("home.example.com", TYPE_A, CLASS_ANY, 0, &[][..]),
+                (
+                    "home.example.com",
+                    TYPE_A,
+                    CLASS_IN,
+                    300,
+                    &[127, 0, 0, 1][..]
+                ),
+                ("home.example.com", TYPE_AAAA, CLASS_ANY, 0, &[][..]),
+                (
+                    "home.example.com",
+                    TYPE_AAAA,
+                    CLASS_IN,
+                    300,
+                    &Ipv6Addr::LOCALHOST.octets()[..]
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rfc2136_update_hmac_sha512() {
+        let (port, server) = udp_server(
+            test_key(TsigAlgorithm::HmacSha512),
+            test_key(TsigAlgorithm::HmacSha512),
+            0,
+        )
+        .await;
+        let provider = provider(port, TsigAlgorithm::HmacSha512);
+
+        let result = provider.update(UPDATE_V4, http()).await.unwrap();
+        assert!(result);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rfc2136_rejects_error_rcode() {
+        let (port, server) = udp_server(
+            test_key(TsigAlgorithm::HmacSha256),
+            test_key(TsigAlgorithm::HmacSha256),
+            5,
+        )
+        .await;
+        let provider = provider(port, TsigAlgorithm::HmacSha256);
+
+        let error = provider.update(UPDATE_V4, http()).await.unwrap_err();
+        assert_eq!(
+            format!("{error:#}"),
+            "failed to update RFC 2136 zone (example.com): server returned REFUSED"
+        );
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rfc2136_rejects_bad_response_signature() {
+        let mut wrong_key = test_key(TsigAlgorithm::HmacSha256);
+        wrong_key.secret = b"other-secret".to_vec();
+        let (port, server) = udp_server(test_key(TsigAlgorithm::HmacSha256), wrong_key, 0).await;
+        let provider = provider(port, TsigAlgorithm::HmacSha256);
+
+        let error = provider.update(UPDATE_V4, http()).await.unwrap_err();
+        assert_eq!(
+            format!("{error:#}"),
+            "failed to update RFC 2136 zone (example.com): server response TSIG signature did not verify"
+        );
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rfc2136_rejects_response_algorithm_mismatch() {
+        let (port, server) = udp_server(
+            test_key(TsigAlgorithm::HmacSha256),
+            test_key(TsigAlgorithm::HmacSha512),
+            0,
+        )
+        .await;
+        let provider = provider(port, TsigAlgorithm::HmacSha256);
+
+        let error = provider.update(UPDATE_V4, http()).await.unwrap_err();
+        assert_eq!(
+            format!("{error:#}"),
+            "failed to update RFC 2136 zone (example.com): server response was signed with an unexpected algorithm (hmac-sha512)"
+        );
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rfc2136_retries_truncated_response_over_tcp() {
+        let key = test_key(TsigAlgorithm::HmacSha256);
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, port)).await.unwrap();
+
+        let udp_key = test_key(TsigAlgorithm::HmacSha256);
+        let udp = tokio::spawn(async move {
+            let mut buf = vec![0; 4096];
+            let (len, peer) = socket.recv_from(&mut buf).await.unwrap();
+            let response = response(&buf[..len], &udp_key, &udp_key, FLAG_TRUNCATED);
+            socket.send_to(&response, peer).await.unwrap();
+        });
+        let tcp = tokio::spawn(async move {
+            let (mut stream, _peer) = listener.accept().await.unwrap();
+            let len = stream.read_u16().await.unwrap();
+            let mut request = vec![0; usize::from(len)];
+            stream.read_exact(&mut request).await.unwrap();
+            let response = response(&request, &key, &key, 0);
+            stream
+                .write_all(&u16::try_from(response.len()).unwrap().to_be_bytes())
+                .await
+                .unwrap();
+            stream.write_all(&response).await.unwrap();
+        });
+
+        let provider = provider(port, TsigAlgorithm::HmacSha256);
+        let result = provider.update(UPDATE_BOTH, http()).await.unwrap();
+        assert!(result);
+        udp.await.unwrap();
+        tcp.await.unwrap();
+    }
+
+    #[test]
+    fn test_rfc2136_validates_config() {
+        let mut provider = provider(53, TsigAlgorithm::HmacSha256);
+        provider.validate_config().unwrap();
+
+        provider.key_secret = "not base64!".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "RFC 2136 key_secret must be valid base64"
+        );
+
+        provider.key_secret = SECRET.into();
+        provider.domains = smallvec![Domain {
+            name: "example.org".into(),
+            ttl: 60,
+        }];
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "RFC 2136 domain (example.org) is not within zone (example.com)"
+        );
+
+        provider.domains = smallvec![];
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "no domains configured for RFC 2136 provider"
+        );
+    }
+}
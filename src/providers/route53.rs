@@ -0,0 +1,805 @@
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use compact_str::CompactString;
+use hmac::{Hmac, Mac};
+use reqwest::Response;
+use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use smallvec::SmallVec;
+use tokio::time::{Instant, sleep};
+use tracing::{debug, info};
+use url::Url;
+
+use crate::ip::{IpUpdate, IpVersion};
+use crate::providers::Provider;
+
+const API_VERSION: &str = "2013-04-01";
+const XML_NAMESPACE: &str = "https://route53.amazonaws.com/doc/2013-04-01/";
+const SERVICE: &str = "route53";
+// A single change batch accepts at most 1000 changes
+const MAX_CHANGES: usize = 1000;
+
+/// AWS Route 53 update provider
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Route53 {
+    hosted_zone_id: CompactString,
+    access_key_id: Option<CompactString>,
+    secret_access_key: Option<SecretString>,
+    session_token: Option<SecretString>,
+    #[serde(default = "default_region")]
+    region: CompactString,
+    #[serde(default)]
+    wait_for_sync: bool,
+    #[serde(default = "default_sync_timeout", with = "humantime_serde")]
+    sync_timeout: Duration,
+    #[serde(skip, default = "default_sync_poll_interval")]
+    sync_poll_interval: Duration,
+    domains: SmallVec<[Domain; 2]>,
+    #[serde(default = "default_api_url")]
+    api_url: String,
+}
+
+fn default_region() -> CompactString {
+    "us-east-1".into()
+}
+
+fn default_sync_timeout() -> Duration {
+    Duration::from_mins(2)
+}
+
+fn default_sync_poll_interval() -> Duration {
+    Duration::from_secs(5)
+}
+
+fn default_api_url() -> String {
+    "https://route53.amazonaws.com".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct Domain {
+    name: CompactString,
+    #[serde(default = "default_ttl")]
+    ttl: u32,
+}
+
+fn default_ttl() -> u32 {
+    300
+}
+
+/// Resolved AWS credentials used to sign a request
+#[derive(Debug)]
+struct Credentials {
+    access_key_id: String,
+    secret_access_key: SecretString,
+    session_token: Option<SecretString>,
+}
+
+impl Route53 {
+    /// Credentials from the config, falling back to the standard AWS env vars.
+    fn credentials(&self) -> Result<Credentials> {
+        let access_key_id = match &self.access_key_id {
+            Some(key) => key.to_string(),
+            None => std::env::var("AWS_ACCESS_KEY_ID")
+                .context("Route 53 access_key_id is not set and AWS_ACCESS_KEY_ID is missing")?,
+        };
+        let secret_access_key = match &self.secret_access_key {
+            Some(secret) => secret.clone(),
+            None => std::env::var("AWS_SECRET_ACCESS_KEY")
+                .context(
+                    "Route 53 secret_access_key is not set and AWS_SECRET_ACCESS_KEY is missing",
+                )?
+                .into(),
+        };
+        // An explicit key pair in the config must not pick up an unrelated env token
+        let session_token = match (&self.session_token, &self.access_key_id) {
+            (Some(token), _) => Some(token.clone()),
+            (None, Some(_)) => None,
+            (None, None) => std::env::var("AWS_SESSION_TOKEN")
+                .ok()
+                .filter(|token| !token.is_empty())
+                .map(SecretString::from),
+        };
+
+        Ok(Credentials {
+            access_key_id,
+            secret_access_key,
+            session_token,
+        })
+    }
+
+    fn zone_id(&self) -> &str {
+        self.hosted_zone_id
+            .trim_start_matches("/hostedzone/")
+            .trim_matches('/')
+    }
+
+    fn endpoint(&self, path: &str) -> Result<Url> {
+        let url = format!(
+            "{}/{API_VERSION}/{path}",
+            self.api_url.trim_end_matches('/')
+        );
+        Url::parse(&url).with_context(|| format!("invalid Route 53 API URL ({url})"))
+    }
+
+    async fn send_signed(
+        &self,
+        request: &HttpClient,
+        method: reqwest::Method,
+        url: Url,
+        body: String,
+    ) -> Result<Response> {
+        let credentials = self.credentials()?;
+        let headers = sign_request(
+            &credentials,
+            &self.region,
+            SERVICE,
+            method.as_str(),
+            &url,
+            body.as_bytes(),
+            Utc::now(),
+        );
+
+        let mut builder = request.request(method, url);
+        for (name, value) in headers {
+            builder = builder.header(name, value);
+        }
+        if !body.is_empty() {
+            builder = builder
+                .header(reqwest::header::CONTENT_TYPE, "text/xml")
+                .body(body);
+        }
+        Ok(builder.send().await?)
+    }
+
+    async fn change_record_sets(&self, request: &HttpClient, update: &IpUpdate) -> Result<String> {
+        let url = self.endpoint(&format!("hostedzone/{}/rrset/", self.zone_id()))?;
+        let body = self.change_batch(update);
+        let response = self
+            .send_signed(request, reqwest::Method::POST, url, body)
+            .await?;
+        let body = parse_route53_response(response, "update Route 53 record sets").await?;
+        let (change_id, status) = parse_change_info(&body)?;
+        info!(
+            "submitted Route 53 change ({change_id}) for hosted zone {}: {status}",
+            self.zone_id()
+        );
+        Ok(change_id)
+    }
+
+    fn change_batch(&self, update: &IpUpdate) -> String {
+        let mut changes = String::new();
+        for domain in &self.domains {
+            for (version, addr) in update.iter() {
+                let record_type = match version {
+                    IpVersion::V4 => "A",
+                    IpVersion::V6 => "AAAA",
+                };
+                let _ = write!(
+                    changes,
+                    "<Change><Action>UPSERT</Action><ResourceRecordSet>\
+                     <Name>{}</Name><Type>{record_type}</Type><TTL>{}</TTL>\
+                     <ResourceRecords><ResourceRecord><Value>{addr}</Value></ResourceRecord></ResourceRecords>\
+                     </ResourceRecordSet></Change>",
+                    xml_escape(&domain.name),
+                    domain.ttl
+                );
+            }
+        }
+
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+             <ChangeResourceRecordSetsRequest xmlns=\"{XML_NAMESPACE}\">\
+             <ChangeBatch><Comment>Updated by DDRS</Comment><Changes>{changes}</Changes></ChangeBatch>\
+             </ChangeResourceRecordSetsRequest>"
+        )
+    }
+
+    async fn wait_for_insync(&self, request: &HttpClient, change_id: &str) -> Result<()> {
+        let change_id = change_id.trim_start_matches("/change/");
+        let url = self.endpoint(&format!("change/{change_id}"))?;
+        let deadline = Instant::now() + self.sync_timeout;
+
+        loop {
+            let response = self
+                .send_signed(request, reqwest::Method::GET, url.clone(), String::new())
+                .await?;
+            let body =
+                parse_route53_response(response, &format!("get Route 53 change ({change_id})"))
+                    .await?;
+            let (_, status) = parse_change_info(&body)?;
+            if status == "INSYNC" {
+                info!("Route 53 change ({change_id}) is INSYNC");
+                return Ok(());
+            }
+            if Instant::now() + self.sync_poll_interval > deadline {
+                return Err(anyhow!(
+                    "Route 53 change ({change_id}) did not reach INSYNC within {:?} (last status {status})",
+                    self.sync_timeout
+                ));
+            }
+            debug!("Route 53 change ({change_id}) is {status}, polling again");
+            sleep(self.sync_poll_interval).await;
+        }
+    }
+}
+
+/// Build the `SigV4` headers for a request, including `Authorization`.
+fn sign_request(
+    credentials: &Credentials,
+    region: &str,
+    service: &str,
+    method: &str,
+    url: &Url,
+    payload: &[u8],
+    now: DateTime<Utc>,
+) -> Vec<(&'static str, String)> {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let host = match url.port() {
+        Some(port) => format!("{}:{port}", url.host_str().unwrap_or_default()),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+
+    let mut canonical_headers = vec![("host", host), ("x-amz-date", amz_date.clone())];
+    if let Some(token) = &credentials.session_token {
+        canonical_headers.push(("x-amz-security-token", token.expose_secret().to_string()));
+    }
+    let signed_headers = canonical_headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let mut query = url
+        .query_pairs()
+        .map(|(key, value)| (uri_encode(&key, true), uri_encode(&value, true)))
+        .collect::<Vec<_>>();
+    query.sort();
+    let canonical_query = query
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let mut canonical_request = format!(
+        "{method}\n{}\n{canonical_query}\n",
+        uri_encode(url.path(), false)
+    );
+    for (name, value) in &canonical_headers {
+        let _ = writeln!(canonical_request, "{name}:{}", value.trim());
+    }
+    let _ = write!(
+        canonical_request,
+        "\n{signed_headers}\n{}",
+        hex_encode(&Sha256::digest(payload))
+    );
+
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex_encode(&Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let secret = format!("AWS4{}", credentials.secret_access_key.expose_secret());
+    let mut key = hmac_sha256(secret.as_bytes(), date.as_bytes());
+    for part in [region, service, "aws4_request"] {
+        key = hmac_sha256(&key, part.as_bytes());
+    }
+    let signature = hex_encode(&hmac_sha256(&key, string_to_sign.as_bytes()));
+
+    let mut headers = vec![
+        (
+            "authorization",
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+                credentials.access_key_id
+            ),
+        ),
+        ("x-amz-date", amz_date),
+    ];
+    if let Some(token) = &credentials.session_token {
+        headers.push(("x-amz-security-token", token.expose_secret().to_string()));
+    }
+    headers
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(hex, "{byte:02x}");
+    }
+    hex
+}
+
+/// RFC 3986 percent-encoding as required by the canonical request.
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(char::from(byte));
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => {
+                let _ = write!(encoded, "%{byte:02X}");
+            }
+        }
+    }
+    encoded
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+async fn parse_route53_response(response: Response, action: &str) -> Result<String> {
+    let status = response.status();
+    let body = response.text().await?;
+    if status.is_success() {
+        return Ok(body);
+    }
+
+    let detail = parse_error_body(&body).or_else(|| body_snippet(&body));
+    let detail = detail.map_or_else(String::new, |detail| format!(": {detail}"));
+
+    Err(anyhow!("failed to {action}: HTTP {status}{detail}"))
+}
+
+/// Extract `Code: Message` from an `ErrorResponse` or `InvalidChangeBatch` body.
+fn parse_error_body(body: &str) -> Option<String> {
+    let document = roxmltree::Document::parse(body).ok()?;
+    let root = document.root_element();
+    let text = |name: &str| {
+        root.descendants()
+            .find(|node| node.has_tag_name(name))
+            .and_then(|node| node.text())
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+    };
+
+    if root.has_tag_name("InvalidChangeBatch") {
+        let messages = root
+            .descendants()
+            .filter(|node| node.has_tag_name("Message"))
+            .filter_map(|node| node.text())
+            .map(str::trim)
+            .collect::<Vec<_>>();
+        return Some(format!("InvalidChangeBatch: {}", messages.join("; ")));
+    }
+
+    match (text("Code"), text("Message")) {
+        (Some(code), Some(message)) => Some(format!("{code}: {message}")),
+        (Some(detail), None) | (None, Some(detail)) => Some(detail.to_string()),
+        (None, None) => None,
+    }
+}
+
+/// Read the change ID and status from a `ChangeInfo` response.
+fn parse_change_info(body: &str) -> Result<(String, String)> {
+    let document =
+        roxmltree::Document::parse(body).context("failed to parse Route 53 change response")?;
+    let text = |name: &str| {
+        document
+            .descendants()
+            .find(|node| node.has_tag_name(name))
+            .and_then(|node| node.text())
+            .map(|text| text.trim().to_string())
+            .ok_or_else(|| anyhow!("Route 53 change response is missing {name}"))
+    };
+
+    Ok((text("Id")?, text("Status")?))
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[async_trait]
+#[typetag::deserialize(name = "route53")]
+impl Provider for Route53 {
+    fn validate_config(&self) -> Result<()> {
+        if self.zone_id().is_empty() {
+            return Err(anyhow!("Route 53 hosted_zone_id must not be empty"));
+        }
+        if self.region.trim().is_empty() {
+            return Err(anyhow!("Route 53 region must not be empty"));
+        }
+        if self.access_key_id.is_some() != self.secret_access_key.is_some() {
+            return Err(anyhow!(
+                "Route 53 access_key_id and secret_access_key must be set together"
+            ));
+        }
+        self.credentials()?;
+        if self.domains.is_empty() {
+            return Err(anyhow!("no domains configured for Route 53 provider"));
+        }
+        // Each domain may produce an A and an AAAA change
+        if self.domains.len() * 2 > MAX_CHANGES {
+            return Err(anyhow!(
+                "Route 53 provider supports at most {} domains",
+                MAX_CHANGES / 2
+            ));
+        }
+        for domain in &self.domains {
+            if domain.name.trim().is_empty() {
+                return Err(anyhow!("Route 53 domain names must not be empty"));
+            }
+        }
+        if self.wait_for_sync && self.sync_timeout.is_zero() {
+            return Err(anyhow!("Route 53 sync_timeout must be greater than zero"));
+        }
+        Ok(())
+    }
+
+    async fn update(&self, update: IpUpdate, request: HttpClient) -> Result<bool> {
+        if update.is_empty() {
+            return Err(anyhow!("no IP addresses supplied for Route 53 update"));
+        }
+
+        let change_id = self.change_record_sets(&request, &update).await?;
+        if self.wait_for_sync {
+            self.wait_for_insync(&request, &change_id).await?;
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use chrono::TimeZone;
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use smallvec::smallvec;
+    use wiremock::{
+        Mock, MockServer, Request, ResponseTemplate,
+        matchers::{header, header_exists, method, path},
+    };
+
+    use super::*;
+
+    const UPDATE_BOTH: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+    const UPDATE_V4: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: None,
+    };
+
+    fn provider(api_url: String) -> Route53 {
+        Route53 {
+            hosted_zone_id: "/hostedzone/Z123".into(),
+            access_key_id: Some("AKIDEXAMPLE".into()),
+            secret_access_key: Some("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".into()),
+            session_token: None,
+            region: default_region(),
+            wait_for_sync: false,
+            sync_timeout: Duration::from_secs(1),
+            sync_poll_interval: Duration::from_millis(10),
+            domains: smallvec![
+                Domain {
+                    name: "example.com".into(),
+                    ttl: 300,
+                },
+                Domain {
+                    name: "www.example.com".into(),
+                    ttl: 60,
+                },
+            ],
+            api_url,
+        }
+    }
+
+    fn change_info(status: &str) -> String {
+        format!(
+            r#"<?xml version="1.0"?>
+<ChangeResourceRecordSetsResponse xmlns="{XML_NAMESPACE}">
+  <ChangeInfo><Id>/change/C2682N5HXP0BZ4</Id><Status>{status}</Status><SubmittedAt>2026-01-01T00:00:00.000Z</SubmittedAt></ChangeInfo>
+</ChangeResourceRecordSetsResponse>"#
+        )
+    }
+
+    #[test]
+    fn test_route53_signature_matches_aws_test_vector() {
+        // get-vanilla from the AWS SigV4 test suite
+        let credentials = Credentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".into(),
+            session_token: None,
+        };
+        let now = Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap();
+        let url = Url::parse("https://example.amazonaws.com/").unwrap();
+
+        let headers = sign_request(&credentials, "us-east-1", "service", "GET", &url, b"", now);
+
+        assert_eq!(
+            headers,
+            vec![
+                (
+                    "authorization",
+                    "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders=host;x-amz-date, Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31".to_string()
+                ),
+                ("x-amz-date", "20150830T123600Z".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_route53_signs_session_token() {
+        let credentials = Credentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "secret".into(),
+            session_token: Some("token".into()),
+        };
+        let url = Url::parse("http://127.0.0.1:8080/2013-04-01/change/C1").unwrap();
+
+        let headers = sign_request(
+            &credentials,
+            "us-east-1",
+            SERVICE,
+            "GET",
+            &url,
+            b"",
+            Utc::now(),
+        );
+
+        assert!(
+            headers[0]
+                .1
+                .contains("SignedHeaders=host;x-amz-date;x-amz-security-token")
+        );
+        assert_eq!(headers[2], ("x-amz-security-token", "token".to_string()));
+    }
+
+    #[test]
+    fn test_route53_deserializes_from_config() {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+[[providers]]
+type = "route53"
+hosted_zone_id = "Z123"
+access_key_id = "AKIDEXAMPLE"
+secret_access_key = "secret"
+wait_for_sync = true
+sync_timeout = "30s"
+
+[[providers.domains]]
+name = "example.com"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.providers.len(), 1);
+        config.providers[0].validate_config().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_route53_upserts_all_records_in_one_batch() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("POST"))
+            .and(path("/2013-04-01/hostedzone/Z123/rrset/"))
+            .and(header("content-type", "text/xml"))
+            .and(header_exists("x-amz-date"))
+            .and(header_exists("authorization"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(change_info("PENDING")))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_BOTH, http).await.unwrap();
+        assert!(result);
+
+        let requests = mock.received_requests().await.unwrap();
+        let body = String::from_utf8(requests[0].body.clone()).unwrap();
+        let document = roxmltree::Document::parse(&body).unwrap();
+        let changes = document
+            .descendants()
+            .filter(|node| node.has_tag_name("ResourceRecordSet"))
+            .map(|node| {
+                node.descendants()
+                    .filter(|child| {
+                        child.has_tag_name("Name")
+                            || child.has_tag_name("Type")
+                            || child.has_tag_name("TTL")
+                            || child.has_tag_name("Value")
+                    })
+                    .filter_map(|child| child.text())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            [
+                "example.com A 300 127.0.0.1",
+                "example.com AAAA 300 ::1",
+                "www.example.com A 60 127.0.0.1",
+                "www.example.com AAAA 60 ::1",
+            ]
+        );
+        assert_eq!(
+            document
+                .descendants()
+                .filter(|node| node.has_tag_name("Action"))
+                .filter_map(|node| node.text())
+                .collect::<Vec<_>>(),
+            ["UPSERT"; 4]
+        );
+
+        let authorization = requests[0]
+            .headers
+            .get("authorization")
+            .unwrap()
+            .to_str()
+            .unwrap();
+        assert!(authorization.starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/"));
+        assert!(authorization.contains("/us-east-1/route53/aws4_request"));
+    }
+
+    #[tokio::test]
+    async fn test_route53_waits_for_insync() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let mut provider = provider(mock.uri());
+        provider.wait_for_sync = true;
+
+        Mock::given(method("POST"))
+            .and(path("/2013-04-01/hostedzone/Z123/rrset/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(change_info("PENDING")))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let polls = AtomicUsize::new(0);
+        Mock::given(method("GET"))
+            .and(path("/2013-04-01/change/C2682N5HXP0BZ4"))
+            .respond_with(move |_: &Request| {
+                let status = if polls.fetch_add(1, Ordering::SeqCst) < 2 {
+                    "PENDING"
+                } else {
+                    "INSYNC"
+                };
+                ResponseTemplate::new(200).set_body_string(change_info(status))
+            })
+            .expect(3)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_V4, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_route53_sync_timeout() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let mut provider = provider(mock.uri());
+        provider.wait_for_sync = true;
+        provider.sync_timeout = Duration::from_millis(50);
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(change_info("PENDING")))
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(change_info("PENDING")))
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Route 53 change (C2682N5HXP0BZ4) did not reach INSYNC within 50ms (last status PENDING)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_route53_surfaces_error_response() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(403).set_body_string(
+                r#"<?xml version="1.0"?>
+<ErrorResponse xmlns="https://route53.amazonaws.com/doc/2013-04-01/">
+  <Error><Type>Sender</Type><Code>SignatureDoesNotMatch</Code><Message>The request signature we calculated does not match the signature you provided.</Message></Error>
+  <RequestId>d1d2e3</RequestId>
+</ErrorResponse>"#,
+            ))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to update Route 53 record sets: HTTP 403 Forbidden: SignatureDoesNotMatch: The request signature we calculated does not match the signature you provided."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_route53_surfaces_invalid_change_batch() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_string(
+                r#"<?xml version="1.0"?>
+<InvalidChangeBatch xmlns="https://route53.amazonaws.com/doc/2013-04-01/">
+  <Messages><Message>RRSet with DNS name www.example.com. is not permitted in zone example.org.</Message></Messages>
+</InvalidChangeBatch>"#,
+            ))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to update Route 53 record sets: HTTP 400 Bad Request: InvalidChangeBatch: RRSet with DNS name www.example.com. is not permitted in zone example.org."
+        );
+    }
+
+    #[test]
+    fn test_route53_validates_config() {
+        let mut provider = provider(default_api_url());
+        provider.validate_config().unwrap();
+
+        provider.secret_access_key = None;
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "Route 53 access_key_id and secret_access_key must be set together"
+        );
+
+        provider.hosted_zone_id = "/hostedzone/".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "Route 53 hosted_zone_id must not be empty"
+        );
+    }
+}
@@ -0,0 +1,462 @@
+use std::net::IpAddr;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use compact_str::CompactString;
+use reqwest_middleware::ClientWithMiddleware as HttpClient;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use smallvec::SmallVec;
+use tracing::warn;
+
+use crate::ip::{IpUpdate, IpVersion};
+use crate::providers::Provider;
+
+/// Technitium DNS Server HTTP API update provider
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct Technitium {
+    api_url: String,
+    token: SecretString,
+    zone: CompactString,
+    domains: SmallVec<[Domain; 2]>,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+struct Domain {
+    name: CompactString,
+    #[serde(default = "default_ttl")]
+    ttl: u32,
+}
+
+fn default_ttl() -> u32 {
+    300
+}
+
+/// Every API call answers HTTP 200 and reports failures in the `status` field
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiResponse<T> {
+    status: String,
+    error_message: Option<String>,
+    response: Option<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecordsResponse {
+    #[serde(default)]
+    records: Vec<Record>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Record {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    ttl: u32,
+    r_data: RecordData,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordData {
+    ip_address: Option<String>,
+}
+
+impl Record {
+    fn address(&self) -> Option<IpAddr> {
+        self.r_data.ip_address.as_deref()?.parse().ok()
+    }
+}
+
+impl Technitium {
+    /// Calls an API endpoint, sending the token and parameters as a form body
+    /// so the token never ends up in a URL
+    async fn call<T: DeserializeOwned>(
+        &self,
+        request: &HttpClient,
+        endpoint: &str,
+        params: &[(&str, &str)],
+        action: &str,
+    ) -> Result<T> {
+        let body = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("token", self.token.expose_secret())
+            .extend_pairs(params)
+            .finish();
+
+        let response = request
+            .post(format!(
+                "{}/api/{endpoint}",
+                self.api_url.trim_end_matches('/')
+            ))
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/x-www-form-urlencoded",
+            )
+            .body(body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            let detail = body_snippet(&body).map_or_else(String::new, |body| format!(": {body}"));
+            return Err(anyhow!("failed to {action}: HTTP {status}{detail}"));
+        }
+
+        let response = serde_json::from_str::<ApiResponse<T>>(&body).map_err(|error| {
+            anyhow!("failed to {action}: unable to parse Technitium response: {error}")
+        })?;
+        if response.status != "ok" {
+            let detail = response.error_message.unwrap_or(response.status);
+            return Err(anyhow!("failed to {action}: {detail}"));
+        }
+        response
+            .response
+            .ok_or_else(|| anyhow!("failed to {action}: Technitium response is missing data"))
+    }
+
+    async fn get_records(&self, request: &HttpClient, domain: &Domain) -> Result<Vec<Record>> {
+        let records: RecordsResponse = self
+            .call(
+                request,
+                "zones/records/get",
+                &[
+                    ("domain", &domain.name),
+                    ("zone", &self.zone),
+                    ("listZone", "false"),
+                ],
+                &format!("get Technitium records for {}", domain.name),
+            )
+            .await?;
+        Ok(records
+            .records
+            .into_iter()
+            .filter(|record| record.name.eq_ignore_ascii_case(&domain.name))
+            .collect())
+    }
+
+    async fn update_domain(
+        &self,
+        request: &HttpClient,
+        domain: &Domain,
+        update: &IpUpdate,
+    ) -> Result<bool> {
+        let records = self.get_records(request, domain).await?;
+        let ttl = domain.ttl.to_string();
+
+        let mut changed = false;
+        for (version, addr) in update.iter() {
+            let record_type = match version {
+                IpVersion::V4 => "A",
+                IpVersion::V6 => "AAAA",
+            };
+            let existing = records
+                .iter()
+                .filter(|record| record.kind == record_type)
+                .collect::<Vec<_>>();
+            if existing
+                .iter()
+                .any(|record| record.address() == Some(addr) && record.ttl == domain.ttl)
+            {
+                continue;
+            }
+
+            let action = format!(
+                "update Technitium domain ({}) {record_type} record",
+                domain.name
+            );
+            let new_address = addr.to_string();
+            if let Some(record) = existing.first() {
+                if existing.len() > 1 {
+                    warn!(
+                        "Technitium domain ({}) has {} {record_type} records, only the first is updated",
+                        domain.name,
+                        existing.len()
+                    );
+                }
+                // Updates identify the record by its current value
+                let old_address = record.r_data.ip_address.as_deref().unwrap_or_default();
+                let _: serde_json::Value = self
+                    .call(
+                        request,
+                        "zones/records/update",
+                        &[
+                            ("domain", &domain.name),
+                            ("zone", &self.zone),
+                            ("type", record_type),
+                            ("ipAddress", old_address),
+                            ("newIpAddress", &new_address),
+                            ("ttl", &ttl),
+                        ],
+                        &action,
+                    )
+                    .await?;
+            } else {
+                let _: serde_json::Value = self
+                    .call(
+                        request,
+                        "zones/records/add",
+                        &[
+                            ("domain", &domain.name),
+                            ("zone", &self.zone),
+                            ("type", record_type),
+                            ("ipAddress", &new_address),
+                            ("ttl", &ttl),
+                        ],
+                        &action,
+                    )
+                    .await?;
+            }
+            changed = true;
+        }
+        Ok(changed)
+    }
+}
+
+fn body_snippet(body: &str) -> Option<String> {
+    const MAX_BODY_CHARS: usize = 200;
+
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let mut end = body.len();
+    for (count, (index, _)) in body.char_indices().enumerate() {
+        if count == MAX_BODY_CHARS {
+            end = index;
+            break;
+        }
+    }
+
+    let mut snippet = body[..end].to_string();
+    if end < body.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+#[async_trait]
+#[typetag::deserialize(name = "technitium")]
+impl Provider for Technitium {
+    fn validate_config(&self) -> Result<()> {
+        if self.api_url.trim().is_empty() {
+            return Err(anyhow!("Technitium api_url must not be empty"));
+        }
+        if self.token.expose_secret().trim().is_empty() {
+            return Err(anyhow!("Technitium token must not be empty"));
+        }
+        if self.zone.trim().is_empty() {
+            return Err(anyhow!("Technitium zone must not be empty"));
+        }
+        if self.domains.is_empty() {
+            return Err(anyhow!("no domains configured for Technitium provider"));
+        }
+
+        let zone = self.zone.to_ascii_lowercase();
+        for domain in &self.domains {
+            let name = domain.name.to_ascii_lowercase();
+            if name != zone && !name.ends_with(&format!(".{zone}")) {
+                return Err(anyhow!(
+                    "Technitium domain ({}) is not within {}",
+                    domain.name,
+                    self.zone
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    async fn update(&self, update: IpUpdate, request: HttpClient) -> Result<bool> {
+        let mut changed = false;
+        for domain in &self.domains {
+            changed |= self.update_domain(&request, domain, &update).await?;
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use reqwest::Client as InnerHttpClient;
+    use reqwest_middleware::ClientBuilder;
+    use serde_json::json;
+    use smallvec::smallvec;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_string, body_string_contains, method, path},
+    };
+
+    use super::*;
+
+    const UPDATE_BOTH: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: Some(Ipv6Addr::LOCALHOST),
+    };
+    const UPDATE_V4: IpUpdate = IpUpdate {
+        v4: Some(Ipv4Addr::LOCALHOST),
+        v6: None,
+    };
+
+    fn provider(api_url: String) -> Technitium {
+        Technitium {
+            api_url,
+            token: "secret".into(),
+            zone: "home.arpa".into(),
+            domains: smallvec![Domain {
+                name: "wan.home.arpa".into(),
+                ttl: 300,
+            }],
+        }
+    }
+
+    fn records(records: &serde_json::Value) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(json!({
+            "status": "ok",
+            "response": {
+                "zone": {"name": "home.arpa", "type": "Primary"},
+                "records": records
+            }
+        }))
+    }
+
+    fn ok() -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(json!({"status": "ok", "response": {}}))
+    }
+
+    #[test]
+    fn test_technitium_deserializes_from_config() {
+        let config = toml::from_str::<crate::config::Config>(
+            r#"
+[[providers]]
+type = "technitium"
+api_url = "http://localhost:5380"
+token = "secret"
+zone = "home.arpa"
+
+[[providers.domains]]
+name = "wan.home.arpa"
+ttl = 60
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.providers.len(), 1);
+        config.providers[0].validate_config().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_technitium_updates_existing_and_adds_missing() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("POST"))
+            .and(path("/api/zones/records/get"))
+            .and(body_string(
+                "token=secret&domain=wan.home.arpa&zone=home.arpa&listZone=false",
+            ))
+            .respond_with(records(&json!([
+                {"name": "wan.home.arpa", "type": "A", "ttl": 300, "disabled": false,
+                 "rData": {"ipAddress": "192.0.2.1"}},
+                {"name": "wan.home.arpa", "type": "TXT", "ttl": 300, "disabled": false,
+                 "rData": {"text": "hello"}}
+            ])))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/zones/records/update"))
+            .and(body_string(
+                "token=secret&domain=wan.home.arpa&zone=home.arpa&type=A&ipAddress=192.0.2.1&newIpAddress=127.0.0.1&ttl=300",
+            ))
+            .respond_with(ok())
+            .expect(1)
+            .mount(&mock)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/zones/records/add"))
+            .and(body_string(
+                "token=secret&domain=wan.home.arpa&zone=home.arpa&type=AAAA&ipAddress=%3A%3A1&ttl=300",
+            ))
+            .respond_with(ok())
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_BOTH, http).await.unwrap();
+        assert!(result);
+    }
+
+    #[tokio::test]
+    async fn test_technitium_skips_unchanged_records() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("POST"))
+            .and(path("/api/zones/records/get"))
+            .respond_with(records(&json!([
+                {"name": "wan.home.arpa", "type": "A", "ttl": 300, "disabled": false,
+                 "rData": {"ipAddress": "127.0.0.1"}}
+            ])))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("ipAddress"))
+            .respond_with(ok())
+            .expect(0)
+            .mount(&mock)
+            .await;
+
+        let result = provider.update(UPDATE_V4, http).await.unwrap();
+        assert!(!result);
+    }
+
+    #[tokio::test]
+    async fn test_technitium_surfaces_errors() {
+        let mock = MockServer::start().await;
+        let http: HttpClient = ClientBuilder::new(InnerHttpClient::new()).build();
+        let provider = provider(mock.uri());
+
+        Mock::given(method("POST"))
+            .and(path("/api/zones/records/get"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "status": "invalid-token",
+                "errorMessage": "Invalid token or session expired."
+            })))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let error = provider.update(UPDATE_V4, http).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to get Technitium records for wan.home.arpa: Invalid token or session expired."
+        );
+    }
+
+    #[test]
+    fn test_technitium_validates_config() {
+        let mut provider = provider("http://localhost:5380".to_string());
+        provider.validate_config().unwrap();
+
+        provider.domains[0].name = "wan.example.com".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "Technitium domain (wan.example.com) is not within home.arpa"
+        );
+
+        provider.token = "".into();
+        assert_eq!(
+            provider.validate_config().unwrap_err().to_string(),
+            "Technitium token must not be empty"
+        );
+    }
+}